@@ -6,6 +6,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("cargo:rerun-if-changed=cmd/");
     println!("cargo:rerun-if-changed=../runtime");
 
+    // only the client spawns the simulator binary
+    if std::env::var_os("CARGO_FEATURE_CLIENT").is_none() {
+        return Ok(());
+    }
+
     let go_mod_download_output = Command::new("go").args(["mod", "download"]).output()?;
 
     if !go_mod_download_output.status.success() {
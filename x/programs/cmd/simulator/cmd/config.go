@@ -127,6 +127,13 @@ const (
 	KeyEd25519   Type = "ed25519"
 	KeySecp256k1 Type = "secp256k1"
 	Uint64       Type = "u64"
+	Uint32       Type = "u32"
+	Uint16       Type = "u16"
+	Uint8        Type = "u8"
+	Char         Type = "char"
+	Enum         Type = "enum"
+	Tuple        Type = "tuple"
+	Bytes        Type = "bytes"
 )
 
 func unmarshalStep(bytes []byte) (*Step, error) {
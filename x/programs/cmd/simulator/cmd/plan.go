@@ -321,7 +321,7 @@ func (c *runCmd) createCallParams(ctx context.Context, db state.Immutable, param
 				return nil, err
 			}
 			cp = append(cp, Parameter{Value: []byte(key), Type: param.Type})
-		case Uint64, Bool:
+		case Uint64, Uint32, Uint16, Uint8, Char, Bool, Enum, Tuple, Bytes:
 			cp = append(cp, param)
 		default:
 			return nil, fmt.Errorf("%w: %s", ErrInvalidParamType, param.Type)
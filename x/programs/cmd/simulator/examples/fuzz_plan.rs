@@ -0,0 +1,35 @@
+//! Runs a [Plan] generated from arbitrary bytes through a dry-run [Client],
+//! checking that it can be serialized for the simulator. The bytes are read
+//! from the file given as the first argument, or from stdin.
+//!
+//! The same check as a `cargo fuzz` target:
+//!
+//! ```ignore
+//! fuzz_target!(|plan: Plan| {
+//!     Client::dry_run().run_plan(plan).unwrap();
+//! });
+//! ```
+//!
+//! Run with `cargo run --example fuzz_plan --features arbitrary -- <file>`.
+
+use arbitrary::{Arbitrary, Unstructured};
+use simulator::{Client, Plan};
+use std::io::Read;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let data = match std::env::args().nth(1) {
+        Some(path) => std::fs::read(path)?,
+        None => {
+            let mut data = Vec::new();
+            std::io::stdin().read_to_end(&mut data)?;
+            data
+        }
+    };
+
+    let plan = Plan::arbitrary_take_rest(Unstructured::new(&data))?;
+    println!("generated a plan with {} steps", plan.steps.len());
+
+    Client::dry_run().run_plan(plan)?;
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! Decoding of the bech32 addresses printed by the simulator, see
+//! [BIP-173](https://github.com/bitcoin/bips/blob/master/bip-0173.mediawiki).
+
+/// The human-readable part of the simulator's addresses.
+pub(crate) const HRP: &str = "matrix";
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const CHECKSUM_LEN: usize = 6;
+
+/// Decodes a bech32 string with the simulator's [`HRP`] into its payload, or
+/// `None` if it is not one.
+pub(crate) fn decode_bech32(s: &str) -> Option<Vec<u8>> {
+    // mixed case strings are invalid
+    if s.bytes().any(|b| b.is_ascii_uppercase()) && s.bytes().any(|b| b.is_ascii_lowercase()) {
+        return None;
+    }
+
+    let s = s.to_ascii_lowercase();
+    let (hrp, data) = s.rsplit_once('1')?;
+
+    if hrp != HRP || data.len() < CHECKSUM_LEN {
+        return None;
+    }
+
+    let data = data
+        .bytes()
+        .map(|c| CHARSET.iter().position(|&x| x == c).map(|v| v as u8))
+        .collect::<Option<Vec<_>>>()?;
+
+    let checksummed = hrp
+        .bytes()
+        .map(|b| b >> 5)
+        .chain([0])
+        .chain(hrp.bytes().map(|b| b & 31))
+        .chain(data.iter().copied());
+
+    if polymod(checksummed) != 1 {
+        return None;
+    }
+
+    from_5_bits(&data[..data.len() - CHECKSUM_LEN])
+}
+
+fn polymod(values: impl Iterator<Item = u8>) -> u32 {
+    const GENERATOR: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+
+    values.fold(1, |chk, value| {
+        let top = chk >> 25;
+        let chk = ((chk & 0x1ff_ffff) << 5) ^ u32::from(value);
+
+        GENERATOR
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (top >> i) & 1 == 1)
+            .fold(chk, |chk, (_, gen)| chk ^ gen)
+    })
+}
+
+/// Regroups 5-bit values into bytes, rejecting non-zero padding.
+fn from_5_bits(data: &[u8]) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut bytes = Vec::with_capacity(data.len() * 5 / 8);
+
+    for &value in data {
+        acc = (acc << 5) | u32::from(value);
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+
+    if bits >= 5 || acc & ((1 << bits) - 1) != 0 {
+        return None;
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_bech32() {
+        let public_key: Vec<u8> = (1..=32).collect();
+        let encoded = "matrix1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5z5tpwxqergd3c8g7rusqeca0t8";
+
+        assert_eq!(decode_bech32(encoded), Some(public_key.clone()));
+        assert_eq!(
+            decode_bech32(&encoded.to_ascii_uppercase()),
+            Some(public_key)
+        );
+
+        // corrupted checksum, wrong hrp and mixed case
+        assert_eq!(decode_bech32(&encoded.replace("0t8", "0t9")), None);
+        assert_eq!(decode_bech32(&encoded.replace("matrix", "token")), None);
+        assert_eq!(decode_bech32(&encoded.replace("matrix", "Matrix")), None);
+    }
+}
@@ -0,0 +1,105 @@
+use std::{
+    io::BufReader,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+use crate::{check_expected_error, Client, ClientError, Plan, PlanResponse, Responses, StepError};
+
+impl Plan<'_> {
+    /// Runs the [Plan] in a simulator spawned from `binary_path` for this run
+    /// only: every step is written at once, then the simulator exits once it
+    /// has answered them all. The simulator has no mode taking a whole plan,
+    /// so it runs in the interpreter mode a [Client] uses.
+    /// # Errors
+    ///
+    /// Returns an error if the simulator cannot be spawned, a step cannot be
+    /// serialized, or the simulator stops before answering every step.
+    pub fn run_batch(&self, binary_path: &Path) -> Result<Vec<PlanResponse>, StepError> {
+        self.run_batch_command(Command::new(binary_path))
+    }
+
+    fn run_batch_command(&self, mut command: Command) -> Result<Vec<PlanResponse>, StepError> {
+        let mut child = command
+            .arg("interpreter")
+            .arg("--cleanup")
+            .arg("--log-level")
+            .arg("error")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(ClientError::from)?;
+
+        let writer = child.stdin.take().ok_or(ClientError::StdIo)?;
+        let reader = child.stdout.take().ok_or(ClientError::StdIo)?;
+
+        // written on another thread, so a full stdout pipe cannot block the writes
+        let (written, responses) = thread::scope(|scope| {
+            let written = scope.spawn(|| {
+                // dropped once written, closing stdin so the simulator exits
+                let mut client = Client::new(writer, std::iter::empty());
+
+                self.steps
+                    .iter()
+                    .try_for_each(|step| client.write_step(self.caller_key, step))
+            });
+            let responses: Vec<_> = Responses::new(BufReader::new(reader)).collect();
+
+            (written.join().expect("plan writer panicked"), responses)
+        });
+
+        child.wait().map_err(ClientError::from)?;
+        written?;
+
+        if responses.len() < self.steps.len() {
+            return Err(StepError::Client(ClientError::Eof));
+        }
+
+        self.steps
+            .iter()
+            .zip(responses)
+            .map(|(step, response)| check_expected_error(step.expect_error.as_deref(), response?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Key, Step};
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_plan_in_batch() {
+        // answers every step read from stdin, numbering them from 0; run
+        // inline rather than from a written file, which a concurrent fork may
+        // still hold open for writing when it is executed
+        let script = r#"
+id=0
+while read -r line; do
+    echo "{\"id\": $id, \"result\": {\"response\": \"\", \"timestamp\": 0}}"
+    id=$((id + 1))
+done
+"#;
+        let simulator = || {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(script).arg("simulator");
+            command
+        };
+
+        let mut plan = Plan::new("alice");
+        plan.create_keys(["alice", "bob"].map(|name| Key::Ed25519(name.into())));
+        let responses = plan.run_batch_command(simulator());
+
+        plan.add_step(Step::create_key(Key::Ed25519("carol".into())).expect_error("exists"));
+        let unexpected = plan.run_batch_command(simulator());
+
+        let ids: Vec<_> = responses.unwrap().iter().map(|resp| resp.base.id).collect();
+        assert_eq!(ids, [0, 1]);
+        assert!(matches!(
+            unexpected,
+            Err(StepError::UnexpectedSuccess { id: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,66 @@
+//! Decoding of the cb58 strings the simulator prints ids as: base58 with a
+//! trailing 4-byte checksum, the last bytes of the payload's sha256 hash.
+
+use sha2::{Digest, Sha256};
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const CHECKSUM_LEN: usize = 4;
+
+/// Decodes a cb58 string into its payload, or `None` if it is not one.
+pub(crate) fn decode(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = decode_base58(s)?;
+
+    if bytes.len() < CHECKSUM_LEN {
+        return None;
+    }
+
+    let checksum = bytes.split_off(bytes.len() - CHECKSUM_LEN);
+    let hash = Sha256::digest(&bytes);
+
+    (hash[hash.len() - CHECKSUM_LEN..] == checksum[..]).then_some(bytes)
+}
+
+fn decode_base58(s: &str) -> Option<Vec<u8>> {
+    // big-endian digits of the decoded number
+    let mut digits: Vec<u8> = Vec::new();
+
+    for c in s.bytes() {
+        let mut carry = ALPHABET.iter().position(|&x| x == c)?;
+
+        for digit in digits.iter_mut().rev() {
+            carry += usize::from(*digit) * 58;
+            *digit = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+
+        while carry > 0 {
+            digits.insert(0, (carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    // every leading '1' is a leading zero byte
+    let zeros = s.bytes().take_while(|&c| c == b'1').count();
+
+    Some([vec![0; zeros], digits].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_cb58() {
+        let id = "2ut4fwdGE5FJG5w89CF3pVCjLrhiqCRZxB7ojtPnigh7QVU51i";
+        let payload = decode(id).unwrap();
+
+        assert_eq!(payload.len(), 32);
+        assert_eq!(payload[..4], [0xfb, 0xc4, 0x7b, 0xf5]);
+
+        // corrupted checksum and invalid characters
+        assert_eq!(decode(&id.replace("51i", "51j")), None);
+        assert_eq!(decode("0OIl"), None);
+
+        assert_eq!(decode_base58("11"), Some(vec![0, 0]));
+    }
+}
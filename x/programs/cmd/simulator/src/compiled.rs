@@ -0,0 +1,157 @@
+use std::{io::Write, time::Duration};
+
+use crate::{
+    check_expected_error, Client, Param, Plan, PlanResponse, SimulatorStep, Step, StepError,
+    StepResult,
+};
+
+/// The JSON the simulator step of an empty caller key starts with.
+const CALLER_KEY_PREFIX: &str = r#"{"callerKey":"""#;
+
+/// A [Plan] serialized ahead of time by [`Client::compile`], to run it many
+/// times with [`Client::run_compiled`] without serializing its steps again.
+/// Only the caller key is filled in on each run.
+#[derive(Clone, Debug)]
+pub struct CompiledPlan {
+    steps: Vec<CompiledStep>,
+}
+
+#[derive(Clone, Debug)]
+struct CompiledStep {
+    template: Template,
+    expect_error: Option<String>,
+    timeout: Option<Duration>,
+}
+
+#[derive(Clone, Debug)]
+enum Template {
+    /// The JSON of the step following its caller key.
+    Json(String),
+    /// A step passing [`Param::CallerAddress`], whose params depend on the
+    /// caller key, so it is serialized on each run.
+    Step(Step),
+}
+
+impl CompiledPlan {
+    /// Returns the number of steps of the plan.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl<W, R> Client<W, R>
+where
+    W: Write,
+    R: Iterator<Item = StepResult>,
+{
+    /// Serializes the steps of the `plan` with the [`ParamEncoding`](crate::ParamEncoding)
+    /// of the [Client], leaving out its caller key.
+    /// # Errors
+    ///
+    /// Returns an error if a step cannot be serialized.
+    pub fn compile(&self, plan: &Plan) -> Result<CompiledPlan, StepError> {
+        let steps = plan
+            .steps
+            .iter()
+            .map(|step| {
+                let template = if step.params.contains(&Param::CallerAddress) {
+                    Template::Step(step.clone())
+                } else {
+                    let json = serde_json::to_string(&SimulatorStep {
+                        caller_key: "",
+                        step,
+                        encoding: self.encoding,
+                    })?;
+
+                    Template::Json(json[CALLER_KEY_PREFIX.len()..].into())
+                };
+
+                Ok(CompiledStep {
+                    template,
+                    expect_error: step.expect_error.clone(),
+                    timeout: step.timeout,
+                })
+            })
+            .collect::<Result<_, StepError>>()?;
+
+        Ok(CompiledPlan { steps })
+    }
+
+    /// Runs a [`CompiledPlan`] as `caller_key`, like [`Client::run_plan`]
+    /// runs the [Plan] it was compiled from.
+    /// # Errors
+    ///
+    /// Returns the first error, without running the remaining steps.
+    pub fn run_compiled(
+        &mut self,
+        plan: &CompiledPlan,
+        caller_key: &str,
+    ) -> Result<Vec<PlanResponse>, StepError> {
+        let quoted_caller_key = serde_json::to_string(caller_key)?;
+
+        plan.steps
+            .iter()
+            .map(|step| {
+                match &step.template {
+                    Template::Json(json) => self.write_step_json(&[
+                        br#"{"callerKey":"#,
+                        quoted_caller_key.as_bytes(),
+                        json.as_bytes(),
+                    ])?,
+                    Template::Step(step) => self.write_step(caller_key, step)?,
+                }
+
+                let response = self.read_response_within(step.timeout)?;
+
+                check_expected_error(step.expect_error.as_deref(), response)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Endpoint, Id, Key};
+    use serde_json::json;
+
+    #[test]
+    fn compiled_run_matches_fresh_run() {
+        let response = json!({ "id": 0, "result": { "response": "", "timestamp": 0 } });
+        let responses = std::iter::repeat_with(|| {
+            Ok(serde_json::from_str::<PlanResponse>(&response.to_string()).unwrap())
+        });
+
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::create_key(Key::Ed25519("bob".into())));
+        plan.add_step(
+            Step::new(
+                Endpoint::Execute,
+                "transfer",
+                1000,
+                vec![Id::from(0).into(), Param::CallerAddress, 10u64.into()],
+            )
+            .with_description("pay \"bob\""),
+        );
+
+        let mut fresh = Client::new(Vec::new(), responses);
+        plan.caller_key = "carol";
+        fresh.run_plan(plan.clone()).unwrap();
+
+        let mut compiled = Client::new(Vec::new(), responses);
+        let steps = compiled.compile(&plan).unwrap();
+        assert_eq!(steps.len(), 2);
+        compiled.run_compiled(&steps, "carol").unwrap();
+
+        assert_eq!(
+            String::from_utf8(compiled.writer).unwrap(),
+            String::from_utf8(fresh.writer).unwrap()
+        );
+    }
+}
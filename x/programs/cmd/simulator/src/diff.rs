@@ -0,0 +1,153 @@
+use crate::{Endpoint, Param, Plan, Step};
+
+/// A difference between two [Plan]s, reported by [`Plan::diff`]. Steps are
+/// indexed by their position, `old` values come from the plan `diff` is
+/// called on and `new` values from the plan it is compared with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlanDiff {
+    CallerKey {
+        old: String,
+        new: String,
+    },
+    Endpoint {
+        step: usize,
+        old: Endpoint,
+        new: Endpoint,
+    },
+    Method {
+        step: usize,
+        old: String,
+        new: String,
+    },
+    MaxUnits {
+        step: usize,
+        old: u64,
+        new: u64,
+    },
+    /// A param of the step differs, or is only passed in one of the plans.
+    Param {
+        step: usize,
+        index: usize,
+        old: Option<Param>,
+        new: Option<Param>,
+    },
+    /// The step is only in the new plan.
+    StepAdded {
+        step: usize,
+    },
+    /// The step is only in the old plan.
+    StepRemoved {
+        step: usize,
+    },
+}
+
+impl Plan<'_> {
+    /// Compares the [Plan] with `other` step by step, returning what differs
+    /// in what is sent to the simulator. Descriptions, expected errors,
+    /// timeouts and dependencies are not compared.
+    #[must_use]
+    pub fn diff(&self, other: &Plan) -> Vec<PlanDiff> {
+        let mut diffs = Vec::new();
+
+        if self.caller_key != other.caller_key {
+            diffs.push(PlanDiff::CallerKey {
+                old: self.caller_key.into(),
+                new: other.caller_key.into(),
+            });
+        }
+
+        for (step, (old, new)) in self.steps.iter().zip(&other.steps).enumerate() {
+            diff_step(step, old, new, &mut diffs);
+        }
+
+        let common = self.steps.len().min(other.steps.len());
+
+        diffs.extend((common..self.steps.len()).map(|step| PlanDiff::StepRemoved { step }));
+        diffs.extend((common..other.steps.len()).map(|step| PlanDiff::StepAdded { step }));
+
+        diffs
+    }
+}
+
+fn diff_step(step: usize, old: &Step, new: &Step, diffs: &mut Vec<PlanDiff>) {
+    if old.endpoint != new.endpoint {
+        diffs.push(PlanDiff::Endpoint {
+            step,
+            old: old.endpoint.clone(),
+            new: new.endpoint.clone(),
+        });
+    }
+
+    if old.method != new.method {
+        diffs.push(PlanDiff::Method {
+            step,
+            old: old.method.clone(),
+            new: new.method.clone(),
+        });
+    }
+
+    if old.max_units != new.max_units {
+        diffs.push(PlanDiff::MaxUnits {
+            step,
+            old: old.max_units,
+            new: new.max_units,
+        });
+    }
+
+    for index in 0..old.params.len().max(new.params.len()) {
+        let (old, new) = (old.params.get(index), new.params.get(index));
+
+        if old != new {
+            diffs.push(PlanDiff::Param {
+                step,
+                index,
+                old: old.cloned(),
+                new: new.cloned(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Id, Key};
+
+    fn transfer(amount: u64) -> Step {
+        Step::new(
+            Endpoint::Execute,
+            "transfer",
+            1000,
+            vec![
+                Id::from(1).into(),
+                Key::Ed25519("bob".into()).into(),
+                amount.into(),
+            ],
+        )
+    }
+
+    #[test]
+    fn reports_single_difference() {
+        let mut old = Plan::new("alice");
+        old.add_step(Step::create_key(Key::Ed25519("bob".into())));
+        old.add_step(Step::create_program("token.wasm"));
+        old.add_step(transfer(10));
+
+        let mut new = old.clone();
+        new.steps[2] = transfer(20);
+
+        assert_eq!(
+            old.diff(&new),
+            [PlanDiff::Param {
+                step: 2,
+                index: 2,
+                old: Some(10u64.into()),
+                new: Some(20u64.into()),
+            }]
+        );
+
+        new.steps.pop();
+        assert_eq!(old.diff(&new), [PlanDiff::StepRemoved { step: 2 }]);
+        assert_eq!(new.diff(&old), [PlanDiff::StepAdded { step: 2 }]);
+    }
+}
@@ -0,0 +1,161 @@
+use arbitrary::{Arbitrary, Result, Unstructured};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::{Endpoint, Id, Key, LenPrefix, Param, Plan, SignatureKind, Step};
+
+const IDENT_START: &[u8] = b"abcdefghijklmnopqrstuvwxyz_";
+const IDENT_CONTINUE: &[u8] = b"abcdefghijklmnopqrstuvwxyz_0123456789";
+
+impl<'a> Arbitrary<'a> for Endpoint {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(
+            u.choose(&[Endpoint::Key, Endpoint::ReadOnly, Endpoint::Execute])?
+                .clone(),
+        )
+    }
+}
+
+impl<'a> Arbitrary<'a> for Key {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let name = identifier(u)?;
+
+        if u.arbitrary()? {
+            Ok(Key::Ed25519(name))
+        } else {
+            Ok(Key::Secp256r1(name))
+        }
+    }
+}
+
+impl<'a> Arbitrary<'a> for SignatureKind {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[SignatureKind::Ed25519, SignatureKind::Secp256r1])?)
+    }
+}
+
+impl<'a> Arbitrary<'a> for LenPrefix {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[LenPrefix::U32, LenPrefix::U64])?)
+    }
+}
+
+/// Generates any [Param] but [`Param::Id`], which only a [Plan] can point at
+/// an earlier step, and [`Param::Placeholder`], which cannot be sent.
+impl<'a> Arbitrary<'a> for Param {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let param = match u.choose_index(6)? {
+            0 => Param::Key(u.arbitrary()?),
+            1 => Param::Timestamp(UNIX_EPOCH + Duration::from_secs(u.arbitrary::<u32>()?.into())),
+            2 => Param::Duration(Duration::from_secs(u.arbitrary()?)),
+            3 => {
+                let algorithm: SignatureKind = u.arbitrary()?;
+                let bytes = u.bytes(algorithm.signature_len())?.to_vec();
+                Param::Signature { algorithm, bytes }
+            }
+            4 => Param::Enum {
+                variant: u.arbitrary()?,
+                fields: (0..u.int_in_range(0..=4)?)
+                    .map(|_| nested_param(u))
+                    .collect::<Result<_>>()?,
+            },
+            _ => nested_param(u)?,
+        };
+
+        Ok(param)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Step {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let endpoint = u.arbitrary()?;
+
+        if endpoint == Endpoint::Key {
+            return Ok(Step::create_key(u.arbitrary()?));
+        }
+
+        Ok(Step::new(
+            endpoint,
+            identifier(u)?,
+            u.arbitrary()?,
+            u.arbitrary()?,
+        ))
+    }
+}
+
+/// Generates a [Plan] whose [`Param::Id`]s only point at earlier steps.
+impl<'a> Arbitrary<'a> for Plan<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut plan = Plan::new(<&str>::arbitrary(u)?);
+
+        for _ in 0..u.arbitrary_len::<Step>()? {
+            let mut step: Step = u.arbitrary()?;
+            let steps = plan.steps.len();
+
+            if step.endpoint != Endpoint::Key && steps > 0 && u.arbitrary()? {
+                let id = Id::from(u.choose_index(steps)?);
+                step.params.insert(0, Param::Id(id));
+            }
+
+            plan.add_step(step);
+        }
+
+        Ok(plan)
+    }
+}
+
+/// Generates a [Param] that can be a field of a [`Param::Enum`].
+fn nested_param(u: &mut Unstructured) -> Result<Param> {
+    let param = match u.choose_index(8)? {
+        0 => Param::U64(u.arbitrary()?),
+        1 => Param::U32(u.arbitrary()?),
+        2 => Param::U16(u.arbitrary()?),
+        3 => Param::U8(u.arbitrary()?),
+        4 => Param::Char(u.arbitrary()?),
+        5 => Param::String(u.arbitrary()?),
+        6 => Param::BytesWithLen {
+            bytes: u.arbitrary()?,
+            len_prefix: u.arbitrary()?,
+        },
+        _ => Param::Bytes(u.arbitrary()?),
+    };
+
+    Ok(param)
+}
+
+/// Generates a valid method or key name.
+fn identifier(u: &mut Unstructured) -> Result<String> {
+    let mut name = String::from(char::from(*u.choose(IDENT_START)?));
+
+    for _ in 0..u.int_in_range(0..=15)? {
+        name.push(char::from(*u.choose(IDENT_CONTINUE)?));
+    }
+
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_point_at_earlier_steps() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let mut u = Unstructured::new(&data);
+
+        while !u.is_empty() {
+            let plan: Plan = u.arbitrary().unwrap();
+
+            for (index, step) in plan.steps.iter().enumerate() {
+                for param in &step.params {
+                    if let Param::Id(id) = param {
+                        assert!(*<&usize>::from(id) < index);
+                    }
+                }
+
+                assert!(crate::method::is_valid(&step.method));
+            }
+
+            assert!(crate::Client::dry_run().run_plan(plan).is_ok());
+        }
+    }
+}
@@ -1,17 +1,105 @@
 use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+use thiserror::Error;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Id(usize);
+const PREFIX: &str = "step_";
+
+#[derive(Error, Debug, PartialEq)]
+#[error("invalid step id: {0:?}")]
+pub struct InvalidId(pub String);
+
+/// The id of a step. Ids returned by a [Plan](crate::Plan) are indexes into
+/// it, and remember which plan they come from so that
+/// [`Client::run_plans_sequential`](crate::Client::run_plans_sequential) can
+/// rebase them, and [`Plan::validate`](crate::Plan::validate) can warn about
+/// ids used by another plan. The tag is not part of the wire form and is
+/// ignored when comparing ids.
+#[derive(Clone, Copy, Debug)]
+pub struct Id {
+    index: usize,
+    plan: Option<u64>,
+}
+
+impl Id {
+    pub(crate) fn with_plan(self, plan: u64) -> Self {
+        Id {
+            plan: Some(plan),
+            ..self
+        }
+    }
+
+    /// Returns the tag of the plan the [Id] comes from, or `None` if it was
+    /// not returned by a plan, e.g. when created with [`Id::from`].
+    pub(crate) fn plan(self) -> Option<u64> {
+        self.plan
+    }
+
+    /// Whether the [Id] points at a step of the plan tagged `plan`: it was
+    /// returned by that plan, or is an untagged index.
+    pub(crate) fn belongs_to(self, plan: u64) -> bool {
+        self.plan.is_none_or(|tag| tag == plan)
+    }
+}
+
+impl PartialEq for Id {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl Eq for Id {}
+
+impl PartialOrd for Id {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Id {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index.cmp(&other.index)
+    }
+}
+
+impl Hash for Id {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
 
 impl From<usize> for Id {
-    fn from(id: usize) -> Self {
-        Id(id)
+    fn from(index: usize) -> Self {
+        Id { index, plan: None }
     }
 }
 
 impl<'a> From<&'a Id> for &'a usize {
     fn from(val: &'a Id) -> Self {
-        &val.0
+        &val.index
+    }
+}
+
+/// Formats the [Id] in its `step_N` wire form.
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{PREFIX}{}", self.index)
+    }
+}
+
+/// Parses an [Id] from its `step_N` wire form.
+impl FromStr for Id {
+    type Err = InvalidId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.strip_prefix(PREFIX)
+            .and_then(|num| num.parse::<usize>().ok())
+            .map(Id::from)
+            .ok_or_else(|| InvalidId(s.into()))
     }
 }
 
@@ -20,8 +108,7 @@ impl Serialize for Id {
     where
         S: serde::Serializer,
     {
-        let s = format!("step_{}", self.0);
-        serializer.serialize_str(&s)
+        serializer.collect_str(self)
     }
 }
 
@@ -31,14 +118,7 @@ impl<'de> Deserialize<'de> for Id {
         D: serde::Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-
-        if !s.starts_with("step_") {
-            return Err(serde::de::Error::custom(r#"missing "step_" prefix"#));
-        }
-
-        let s = s.trim_start_matches("step_");
-        let id = s.parse().map_err(serde::de::Error::custom)?;
-        Ok(Id(id))
+        s.parse().map_err(serde::de::Error::custom)
     }
 }
 
@@ -48,11 +128,30 @@ mod tests {
 
     #[test]
     fn id_serde() {
-        let id = Id(42);
+        let id = Id::from(42);
         let s = serde_json::to_string(&id).unwrap();
         assert_eq!(s, "\"step_42\"");
 
         let id: Id = serde_json::from_str(&s).unwrap();
-        assert_eq!(id, Id(42));
+        assert_eq!(id, Id::from(42));
+
+        for invalid in [r#""42""#, r#""step_""#, r#""step_step_1""#, r#""step_-1""#] {
+            assert!(serde_json::from_str::<Id>(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn id_from_str() {
+        assert_eq!("step_7".parse(), Ok(Id::from(7)));
+        assert_eq!(Id::from(7).to_string(), "step_7");
+        assert_eq!("7".parse::<Id>(), Err(InvalidId("7".into())));
+    }
+
+    #[test]
+    fn id_ordering() {
+        let mut ids = [3, 0, 12, 2].map(Id::from);
+        ids.sort();
+
+        assert_eq!(ids, [0, 2, 3, 12].map(Id::from));
     }
 }
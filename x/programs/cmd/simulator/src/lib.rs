@@ -2,22 +2,74 @@
 //! developers to construct tests for their programs completely in Rust.
 //! Alternatively the `Plan` can be written in JSON and passed to the
 //! Simulator binary directly.
+//!
+//! The `client` feature, on by default, builds the simulator binary and
+//! provides the [`ClientBuilder`] spawning it. Without it, only the types
+//! describing plans and responses are available, and go is not needed.
 
 use base64::{engine::general_purpose::STANDARD as b64, Engine};
 use borsh::BorshDeserialize;
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{
+    de::DeserializeOwned, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime, SystemTimeError, UNIX_EPOCH},
+};
+#[cfg(feature = "client")]
 use std::{
-    io::{BufRead, BufReader, Write},
-    path::Path,
-    process::{Child, Command, Stdio},
+    fs::File,
+    io::BufReader,
+    process::{Child, ChildStdin, Command, Stdio},
 };
 use thiserror::Error;
 
+mod address;
+#[cfg(feature = "client")]
+mod batch;
+mod cb58;
+mod compiled;
+mod diff;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
 mod id;
+mod method;
+mod outcome;
+#[cfg(feature = "client")]
+mod parallel;
+mod responses;
+mod validate;
+
+pub use compiled::CompiledPlan;
+pub use diff::PlanDiff;
+pub use id::{Id, InvalidId};
+pub use method::{InvalidMethod, Method};
+pub use outcome::{RunOutcome, RunReport};
+#[cfg(feature = "client")]
+use responses::Tee;
+pub use responses::{DryRunResponses, Responses, ThreadedResponses};
+pub use validate::{ValidationError, ValidationWarning};
 
-pub use id::Id;
+const PROGRAM_CREATE: &str = "program_create";
 
 /// The endpoint to call for a [Step].
+///
+/// There is no endpoint to advance the simulator's clock: programs are not
+/// given a block timestamp, so time-dependent logic (vesting, cooldowns, ...)
+/// should take the current time as an argument, e.g. a [`Param::Timestamp`],
+/// and tests can pass whatever time they need.
+///
+/// Nor is there an endpoint to read raw state keys: a program's state is only
+/// reachable through its own functions, so to assert on internal state, give
+/// the program a read-only getter for it and call it with
+/// [`Endpoint::ReadOnly`].
 #[derive(Debug, Serialize, PartialEq, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum Endpoint {
@@ -43,44 +95,275 @@ pub struct Step {
     pub max_units: u64,
     /// The parameters to pass to the method.
     pub params: Vec<Param>,
+    /// An optional human-readable description of the step. It is not sent to
+    /// the simulator, whose command line parsing breaks on free text such as
+    /// quotes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// An error the step is expected to fail with. The [Client] checks the
+    /// step's error contains this text, and fails the run with
+    /// [`StepError::UnexpectedSuccess`] if the step succeeds. It is not sent
+    /// to the simulator.
+    #[serde(skip)]
+    pub expect_error: Option<String>,
+    /// How long to wait for the response of this step, overriding the
+    /// client's [`read_timeout`](ClientBuilder::read_timeout). It is not sent
+    /// to the simulator.
+    #[serde(skip)]
+    pub timeout: Option<Duration>,
+    /// Steps this step depends on besides those its [`Param::Id`]s reference,
+    /// see [`Step::depends_on`]. They are not sent to the simulator.
+    #[serde(skip)]
+    pub dependencies: Vec<Id>,
 }
 
-#[derive(Debug, Serialize, PartialEq)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, PartialEq)]
 pub struct SimulatorStep<'a> {
     /// The key of the caller used in each step of the plan.
     pub caller_key: &'a str,
-    #[serde(flatten)]
     pub step: &'a Step,
+    /// The encoding used for the params of the step.
+    pub encoding: ParamEncoding,
+}
+
+impl Serialize for SimulatorStep<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let Step {
+            endpoint,
+            method,
+            max_units,
+            params,
+            description: _,
+            expect_error: _,
+            timeout: _,
+            dependencies: _,
+        } = self.step;
+
+        let params: Vec<_> = params
+            .iter()
+            .map(|param| match param {
+                Param::CallerAddress => {
+                    Cow::Owned(Param::Key(Key::Ed25519(self.caller_key.into())))
+                }
+                param => Cow::Borrowed(param),
+            })
+            .collect();
+        let params: Vec<_> = params
+            .iter()
+            .map(|param| param.encode(self.encoding))
+            .collect();
+
+        let mut state = serializer.serialize_struct("SimulatorStep", 5)?;
+        state.serialize_field("callerKey", self.caller_key)?;
+        state.serialize_field("endpoint", endpoint)?;
+        state.serialize_field("method", method)?;
+        state.serialize_field("maxUnits", max_units)?;
+        state.serialize_field("params", &params)?;
+        state.end()
+    }
 }
 
 impl Step {
-    /// Create a [Step] that creates a key.
+    /// Create a [Step] calling `method` on the `endpoint`, with no
+    /// description, expected error, timeout or dependencies.
     #[must_use]
-    pub fn create_key(key: Key) -> Self {
+    pub fn new<S: Into<String>>(
+        endpoint: Endpoint,
+        method: S,
+        max_units: u64,
+        params: Vec<Param>,
+    ) -> Self {
         Self {
-            endpoint: Endpoint::Key,
-            method: "create_key".into(),
-            max_units: 0,
-            params: vec![Param::Key(key)],
+            endpoint,
+            method: method.into(),
+            max_units,
+            params,
+            description: None,
+            expect_error: None,
+            timeout: None,
+            dependencies: Vec::new(),
         }
     }
 
+    /// Create a [Step] that creates a key. The key material is random, see
+    /// [Key].
+    #[must_use]
+    pub fn create_key(key: Key) -> Self {
+        Self::new(Endpoint::Key, "create_key", 0, vec![Param::Key(key)])
+    }
+
     /// Create a [Step] that creates a program.
     #[must_use]
     pub fn create_program<P: AsRef<Path>>(path: P) -> Self {
         let path = path.as_ref().to_string_lossy();
 
-        Self {
-            endpoint: Endpoint::Execute,
-            method: "program_create".into(),
-            max_units: 0,
-            params: vec![Param::String(path.into())],
+        Self::new(
+            Endpoint::Execute,
+            PROGRAM_CREATE,
+            0,
+            vec![Param::String(path.into())],
+        )
+    }
+
+    /// Create a [Step] that creates the program compiled from the crate
+    /// `crate_name` with the `profile`, found at the conventional
+    /// `<dir>/wasm32-unknown-unknown/<profile>/<crate_name>.wasm` path. The
+    /// `<dir>` is the first of `$CARGO_TARGET_DIR`, `build` (where
+    /// `wasmlanche_sdk::build::build_wasm_on_test` puts it) or `target` that
+    /// holds the program, relative to the current directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StepError::ProgramNotFound`] if none of them holds the program.
+    pub fn create_program_from_crate(
+        crate_name: &str,
+        profile: Profile,
+    ) -> Result<Self, StepError> {
+        let dirs = std::env::var_os("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .into_iter()
+            .chain(["build", "target"].map(PathBuf::from));
+
+        find_artifact(dirs, crate_name, profile).map(Self::create_program)
+    }
+
+    /// Expect the [Step] to fail with an error containing `error`.
+    #[must_use]
+    pub fn expect_error<S: Into<String>>(mut self, error: S) -> Self {
+        self.expect_error = Some(error.into());
+        self
+    }
+
+    /// Declare that the [Step] depends on the steps `ids`, e.g. on state they
+    /// set up, even though none of its params reference them. Dependencies
+    /// are not sent to the simulator, which runs steps in order; they are
+    /// checked by [`Plan::validate`], see [`Plan::execution_order`].
+    #[must_use]
+    pub fn depends_on(mut self, ids: &[Id]) -> Self {
+        self.dependencies.extend_from_slice(ids);
+        self
+    }
+
+    /// Returns the steps the [Step] references with [`Param::Id`]s or
+    /// depends on.
+    fn referenced_ids(&self) -> impl Iterator<Item = &Id> {
+        self.params
+            .iter()
+            .filter_map(|param| match param {
+                Param::Id(id) => Some(id),
+                _ => None,
+            })
+            .chain(&self.dependencies)
+    }
+
+    /// Appends the given `params` to those of the [Step], skipping the
+    /// `None`s, e.g. to leave out optional trailing arguments.
+    #[must_use]
+    pub fn with_optional_params<I>(mut self, params: I) -> Self
+    where
+        I: IntoIterator<Item = Option<Param>>,
+    {
+        self.params.extend(params.into_iter().flatten());
+        self
+    }
+
+    /// Wait at most `timeout` for the response of the [Step].
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Serializes the [Step] as it is sent to the simulator, without the
+    /// caller key, e.g. for logging.
+    /// # Errors
+    ///
+    /// Returns an error if a [Param] cannot be serialized.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Attach a human-readable description to the [Step].
+    #[must_use]
+    pub fn with_description<S: Into<String>>(mut self, description: S) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Sets `max_units` to the whole number of units `budget` buys at `price`
+    /// per unit. The simulator has no notion of a unit price, so this is only
+    /// arithmetic: `price` and `budget` are in whatever unit the caller uses.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `price` is zero.
+    #[must_use]
+    pub fn with_budget(mut self, price: u64, budget: u64) -> Self {
+        assert!(price != 0, "unit price must not be zero");
+        self.max_units = budget / price;
+        self
+    }
+}
+
+/// The cargo profile a program was compiled with, see
+/// [`Step::create_program_from_crate`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Profile {
+    #[default]
+    Debug,
+    Release,
+}
+
+impl Profile {
+    /// The name of the directory cargo puts the artifacts of the profile in.
+    #[must_use]
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            Profile::Debug => "debug",
+            Profile::Release => "release",
         }
     }
 }
 
+/// Returns the canonical path of the wasm program of `crate_name` in the
+/// first of the target `dirs` that holds it.
+fn find_artifact(
+    dirs: impl IntoIterator<Item = PathBuf>,
+    crate_name: &str,
+    profile: Profile,
+) -> Result<PathBuf, StepError> {
+    let file_name = format!("{}.wasm", crate_name.replace('-', "_"));
+    let mut tried = Vec::new();
+
+    for dir in dirs {
+        let path = dir
+            .join("wasm32-unknown-unknown")
+            .join(profile.dir_name())
+            .join(&file_name);
+
+        if let Ok(path) = path.canonicalize() {
+            return Ok(path);
+        }
+
+        tried.push(path);
+    }
+
+    Err(StepError::ProgramNotFound(tried))
+}
+
 /// The algorithm used to generate the key along with a [String] identifier for the key.
+///
+/// The simulator generates the key material at random and cannot derive it
+/// from a seed, so the address of a key differs between runs. Refer to keys
+/// by their identifier rather than by address, and read the address of a key
+/// created in the current run with [`PlanResult::key_address`].
+///
+/// A [Key] cannot be made from public key bytes either, e.g. returned by a
+/// program: the simulator only signs as keys it created. To pass such a key
+/// to a program, use [`Param::address_from_public_key`].
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "lowercase")]
 #[serde(tag = "type", content = "value")]
@@ -91,44 +374,619 @@ pub enum Key {
     Secp256r1(String),
 }
 
+impl std::fmt::Display for Key {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Key::Ed25519(name) => write!(f, "ed25519:{name}"),
+            Key::Secp256r1(name) => write!(f, "secp256r1:{name}"),
+        }
+    }
+}
+
+impl Key {
+    /// Returns the name the key was created with, which identifies it as a caller.
+    #[must_use]
+    pub fn identifier(&self) -> &str {
+        match self {
+            Key::Ed25519(name) | Key::Secp256r1(name) => name,
+        }
+    }
+}
+
+/// The identifier of the key calling a [Step], taken from a string or a [Key].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CallerKey<'a>(&'a str);
+
+impl<'a> CallerKey<'a> {
+    #[must_use]
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+}
+
+impl<'a> From<&'a str> for CallerKey<'a> {
+    fn from(key: &'a str) -> Self {
+        CallerKey(key)
+    }
+}
+
+impl<'a> From<&'a String> for CallerKey<'a> {
+    fn from(key: &'a String) -> Self {
+        CallerKey(key)
+    }
+}
+
+impl<'a> From<&'a Key> for CallerKey<'a> {
+    fn from(key: &'a Key) -> Self {
+        CallerKey(key.identifier())
+    }
+}
+
+/// An argument of a [Step].
+///
+/// Each [Param] is sent to the simulator as a JSON object
+/// `{"type": <tag>, "value": <value>}`, where the tag is given by
+/// [`Param::type_tag`] and, with the default [`ParamEncoding::Base64`], the
+/// value is the base64 encoding of the bytes passed to the program. Ids and
+/// strings are sent as their text, which the simulator resolves, and keys as
+/// their name.
 // TODO:
 // add `Cow` types for borrowing
 #[derive(Clone, Debug, PartialEq)]
 pub enum Param {
     U64(u64),
+    /// The smaller integers have no `From` impl, so that an untyped integer
+    /// such as `10.into()` stays a [`Param::U64`]; build them explicitly,
+    /// e.g. `Param::U32(10)`.
+    U32(u32),
+    U16(u16),
+    U8(u8),
+    /// A `char`, passed to the program as the `u32` of its unicode scalar value.
+    Char(char),
     String(String),
     Id(Id),
+    /// The id of a program created in the simulator, as given by
+    /// [`PlanResult::id`] and parsed with [`Param::from_program_id`]. Unlike a
+    /// [`Param::Id`] it does not depend on the step that created the program,
+    /// e.g. to call it from another session.
+    ProgramId([u8; 32]),
     Key(Key),
+    /// A point in time, passed to the program as a `u64` of whole seconds
+    /// since the unix epoch. This is the same unit as [`PlanResult::timestamp`].
+    Timestamp(SystemTime),
+    /// A span of time, passed to the program as a `u64` of whole seconds.
+    Duration(Duration),
+    /// A borsh-encoded enum: the `variant` index byte followed by the
+    /// borsh encoding of each of the variant's `fields`, e.g. strings are
+    /// length-prefixed. Ids and keys are resolved by the simulator, so they
+    /// cannot be used as fields.
+    Enum {
+        variant: u8,
+        fields: Vec<Param>,
+    },
+    /// Raw bytes, passed to the program as-is without a length prefix, e.g.
+    /// for fixed-size arrays such as addresses or hashes.
+    Bytes(Vec<u8>),
+    /// Bytes passed to the program after their length, as a little-endian
+    /// integer of the given width, e.g. for programs whose byte sequences do
+    /// not follow borsh's `u32` length prefix.
+    BytesWithLen {
+        bytes: Vec<u8>,
+        len_prefix: LenPrefix,
+    },
+    /// A list of strings, encoded as borsh encodes a `Vec<String>`: the `u32`
+    /// number of strings, then each string's `u32` length and bytes.
+    StringVec(Vec<String>),
+    /// A detached signature, passed to the program as its raw bytes like
+    /// [`Param::Bytes`]. Use [`Param::signature`] to check its length.
+    Signature {
+        algorithm: SignatureKind,
+        bytes: Vec<u8>,
+    },
+    /// A param sent exactly as given, as `{"type": type_tag, "value":
+    /// value_base64}`, for types the simulator supports but [Param] does not
+    /// model yet. It is sent the same way with every [`ParamEncoding`].
+    Raw {
+        type_tag: String,
+        value_base64: String,
+    },
+    /// A named value to [bind](Plan::bind) before the plan runs, e.g. to reuse
+    /// a plan with values that differ between environments. Running a step
+    /// with an unbound placeholder fails without sending it.
+    Placeholder(String),
+    /// A borsh-encoded tuple: the borsh encoding of each of its fields,
+    /// concatenated without a length prefix, unlike a `Vec`. The same fields
+    /// as a [`Param::Enum`] are supported.
+    Tuple(Vec<Param>),
+    /// The address of the caller of the step, sent as the [`Key::Ed25519`]
+    /// named after the caller key, which the simulator resolves to its address
+    /// like any other key. It cannot be sent outside of a step.
+    CallerAddress,
+}
+
+/// The width of the length prefix of a [`Param::BytesWithLen`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LenPrefix {
+    /// A `u32` prefix, as borsh encodes a `Vec<u8>`.
+    U32,
+    /// A `u64` prefix.
+    U64,
+}
+
+impl std::fmt::Display for LenPrefix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LenPrefix::U32 => write!(f, "u32"),
+            LenPrefix::U64 => write!(f, "u64"),
+        }
+    }
+}
+
+/// The algorithm of a [`Param::Signature`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureKind {
+    /// A 64 byte ed25519 signature.
+    Ed25519,
+    /// A 64 byte secp256r1 signature, in its compact `r || s` form.
+    Secp256r1,
+}
+
+impl SignatureKind {
+    /// The length in bytes of a signature of this kind.
+    #[must_use]
+    pub fn signature_len(self) -> usize {
+        match self {
+            SignatureKind::Ed25519 | SignatureKind::Secp256r1 => 64,
+        }
+    }
+}
+
+impl std::fmt::Display for SignatureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureKind::Ed25519 => write!(f, "ed25519"),
+            SignatureKind::Secp256r1 => write!(f, "secp256r1"),
+        }
+    }
+}
+
+/// The length in bytes of an address, a type prefix followed by a public key.
+pub const ADDRESS_LEN: usize = 33;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ParamError {
+    #[error("invalid hex string: {0:?}")]
+    InvalidHex(String),
+    #[error("invalid address: {0:?}")]
+    InvalidAddress(String),
+    #[error("invalid program id: {0:?}")]
+    InvalidProgramId(String),
+    #[error("ed25519 public key must be {expected} bytes, got {actual}")]
+    InvalidPublicKeyLength { expected: usize, actual: usize },
+    #[error("{algorithm} signature must be {expected} bytes, got {actual}")]
+    InvalidSignatureLength {
+        algorithm: SignatureKind,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+/// The wire format of [Param] values sent to the simulator. A new variant
+/// is added when the simulator changes the format it reads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParamEncoding {
+    /// Values are the base64 encoding of their bytes, e.g.
+    /// `{"type": "u64", "value": "KgAAAAAAAAA="}`. This is what the current
+    /// simulator expects.
+    #[default]
+    Base64,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "lowercase", tag = "type", content = "value")]
 enum StringParam {
     U64(String),
+    U32(String),
+    U16(String),
+    U8(String),
+    Char(String),
     String(String),
     Id(String),
+    Enum(String),
+    Tuple(String),
+    Bytes(String),
 }
 
-impl Serialize for Param {
+#[derive(Serialize)]
+struct RawParam<'a> {
+    #[serde(rename = "type")]
+    type_tag: &'a str,
+    value: &'a str,
+}
+
+/// A [Param] serialized with a specific [`ParamEncoding`].
+#[derive(Debug)]
+pub struct EncodedParam<'a> {
+    param: &'a Param,
+    encoding: ParamEncoding,
+}
+
+impl Param {
+    /// Returns the `type` tag the [Param] is sent to the simulator with.
+    #[must_use]
+    pub fn type_tag(&self) -> &str {
+        match self {
+            Param::U64(_) | Param::Timestamp(_) | Param::Duration(_) => "u64",
+            Param::U32(_) => "u32",
+            Param::U16(_) => "u16",
+            Param::U8(_) => "u8",
+            Param::Char(_) => "char",
+            Param::String(_) => "string",
+            Param::Id(_) | Param::ProgramId(_) => "id",
+            Param::Key(Key::Ed25519(_)) => "ed25519",
+            Param::Key(Key::Secp256r1(_)) => "secp256r1",
+            Param::Enum { .. } => "enum",
+            Param::Tuple(_) => "tuple",
+            Param::Bytes(_)
+            | Param::BytesWithLen { .. }
+            | Param::StringVec(_)
+            | Param::Signature { .. } => "bytes",
+            Param::Raw { type_tag, .. } => type_tag,
+            Param::Placeholder(_) => "placeholder",
+            Param::CallerAddress => "ed25519",
+        }
+    }
+
+    /// Returns a serializable view of the [Param] using the given `encoding`.
+    #[must_use]
+    pub fn encode(&self, encoding: ParamEncoding) -> EncodedParam<'_> {
+        EncodedParam {
+            param: self,
+            encoding,
+        }
+    }
+
+    /// Creates a [`Param::Signature`], checking that `bytes` has the length
+    /// of an `algorithm` signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` has the wrong length.
+    pub fn signature(algorithm: SignatureKind, bytes: Vec<u8>) -> Result<Self, ParamError> {
+        check_signature(algorithm, &bytes)?;
+        Ok(Param::Signature { algorithm, bytes })
+    }
+
+    /// Creates a [`Param::Bytes`] from a hex string, with or without a `0x` prefix.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `hex` is not an even number of hex digits.
+    pub fn from_hex(hex: &str) -> Result<Self, ParamError> {
+        let digits = hex.strip_prefix("0x").unwrap_or(hex);
+        let invalid = || ParamError::InvalidHex(hex.into());
+
+        if !digits.len().is_multiple_of(2) || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| invalid()))
+            .collect::<Result<_, _>>()
+            .map(Param::Bytes)
+    }
+
+    /// Creates a [`Param::Bytes`] holding the [`ADDRESS_LEN`]-byte address of
+    /// the ed25519 `public_key`, prefixed with the `0` address type as the
+    /// simulator does for keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `public_key` is not 32 bytes long.
+    pub fn address_from_public_key(public_key: &[u8]) -> Result<Self, ParamError> {
+        if public_key.len() != ADDRESS_LEN - 1 {
+            return Err(ParamError::InvalidPublicKeyLength {
+                expected: ADDRESS_LEN - 1,
+                actual: public_key.len(),
+            });
+        }
+
+        Ok(Param::Bytes([&[0], public_key].concat()))
+    }
+
+    /// Creates a [`Param::Bytes`] holding the [`ADDRESS_LEN`]-byte address
+    /// written as `address`, either in the bech32 form the simulator prints
+    /// for created keys, e.g. `matrix1...`, or as hex. A bech32 public key is
+    /// prefixed with the `0` address type, as the simulator does for keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `address` is neither form or not an address.
+    pub fn address_from_str(address: &str) -> Result<Self, ParamError> {
+        let invalid = || ParamError::InvalidAddress(address.into());
+
+        let bytes = match address::decode_bech32(address) {
+            Some(public_key) if public_key.len() == ADDRESS_LEN - 1 => {
+                return Param::address_from_public_key(&public_key);
+            }
+            Some(bytes) => bytes,
+            None => match Param::from_hex(address) {
+                Ok(Param::Bytes(bytes)) => bytes,
+                _ => return Err(invalid()),
+            },
+        };
+
+        if bytes.len() != ADDRESS_LEN {
+            return Err(invalid());
+        }
+
+        Ok(Param::Bytes(bytes))
+    }
+
+    /// Creates a [`Param::ProgramId`] from the cb58 id the simulator returned
+    /// for a created program, see [`PlanResult::id`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `id` is not a cb58-encoded 32 byte id.
+    pub fn from_program_id(id: &str) -> Result<Self, ParamError> {
+        cb58::decode(id)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(Param::ProgramId)
+            .ok_or_else(|| ParamError::InvalidProgramId(id.into()))
+    }
+
+    /// Replaces the [`Param::Placeholder`]s named `name`, including nested
+    /// ones, with `value`.
+    fn bind(&mut self, name: &str, value: &Param) {
+        match self {
+            Param::Placeholder(placeholder) if placeholder == name => *self = value.clone(),
+            Param::Enum { fields, .. } | Param::Tuple(fields) => {
+                for field in fields {
+                    field.bind(name, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Appends the borsh encoding of the [Param], as a field of another one, to `bytes`.
+    fn write_nested(&self, bytes: &mut Vec<u8>) -> Result<(), String> {
+        match self {
+            Param::U64(num) => bytes.extend(num.to_le_bytes()),
+            Param::U32(num) => bytes.extend(num.to_le_bytes()),
+            Param::U16(num) => bytes.extend(num.to_le_bytes()),
+            Param::U8(num) => bytes.push(*num),
+            Param::Char(c) => bytes.extend(u32::from(*c).to_le_bytes()),
+            Param::String(text) => borsh::to_writer(bytes, text).map_err(|err| err.to_string())?,
+            Param::StringVec(texts) => {
+                borsh::to_writer(bytes, texts).map_err(|err| err.to_string())?;
+            }
+            Param::Timestamp(time) => {
+                let seconds = unix_seconds(time).map_err(|err| err.to_string())?;
+                bytes.extend(seconds.to_le_bytes());
+            }
+            Param::Duration(duration) => bytes.extend(duration.as_secs().to_le_bytes()),
+            Param::Bytes(raw) => bytes.extend(raw),
+            Param::BytesWithLen {
+                bytes: raw,
+                len_prefix,
+            } => {
+                match len_prefix {
+                    LenPrefix::U32 => {
+                        let len = u32::try_from(raw.len())
+                            .map_err(|_| format!("{} bytes overflow a u32 length", raw.len()))?;
+                        bytes.extend(len.to_le_bytes());
+                    }
+                    LenPrefix::U64 => bytes.extend((raw.len() as u64).to_le_bytes()),
+                }
+                bytes.extend(raw);
+            }
+            Param::ProgramId(id) => bytes.extend(id),
+            Param::Signature {
+                algorithm,
+                bytes: raw,
+            } => {
+                check_signature(*algorithm, raw).map_err(|err| err.to_string())?;
+                bytes.extend(raw);
+            }
+            Param::Enum { variant, fields } => {
+                bytes.push(*variant);
+
+                for field in fields {
+                    field.write_nested(bytes)?;
+                }
+            }
+            Param::Tuple(fields) => {
+                for field in fields {
+                    field.write_nested(bytes)?;
+                }
+            }
+            Param::Id(_) | Param::Key(_) | Param::Raw { .. } | Param::CallerAddress => {
+                return Err(format!("{self} cannot be nested in another param"));
+            }
+            Param::Placeholder(name) => return Err(format!("unbound placeholder {name:?}")),
+        }
+
+        Ok(())
+    }
+}
+
+fn check_signature(algorithm: SignatureKind, bytes: &[u8]) -> Result<(), ParamError> {
+    let expected = algorithm.signature_len();
+
+    if bytes.len() == expected {
+        Ok(())
+    } else {
+        Err(ParamError::InvalidSignatureLength {
+            algorithm,
+            expected,
+            actual: bytes.len(),
+        })
+    }
+}
+
+fn unix_seconds(time: &SystemTime) -> Result<u64, SystemTimeError> {
+    time.duration_since(UNIX_EPOCH)
+        .map(|since_epoch| since_epoch.as_secs())
+}
+
+impl Serialize for EncodedParam<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        match self {
-            Param::U64(num) => {
+        match (self.encoding, self.param) {
+            (_, Param::Placeholder(name)) => Err(serde::ser::Error::custom(format!(
+                "unbound placeholder {name:?}"
+            ))),
+            (_, Param::CallerAddress) => Err(serde::ser::Error::custom(
+                "the caller address can only be sent in a step",
+            )),
+            (encoding, Param::Timestamp(time)) => {
+                let seconds = unix_seconds(time).map_err(serde::ser::Error::custom)?;
+                Param::U64(seconds).encode(encoding).serialize(serializer)
+            }
+            (encoding, Param::Duration(duration)) => Param::U64(duration.as_secs())
+                .encode(encoding)
+                .serialize(serializer),
+            (
+                _,
+                Param::Raw {
+                    type_tag,
+                    value_base64,
+                },
+            ) => RawParam {
+                type_tag,
+                value: value_base64,
+            }
+            .serialize(serializer),
+            (ParamEncoding::Base64, Param::U64(num)) => {
                 Serialize::serialize(&StringParam::U64(b64.encode(num.to_le_bytes())), serializer)
             }
-            Param::String(text) => {
+            (ParamEncoding::Base64, Param::U32(num)) => {
+                Serialize::serialize(&StringParam::U32(b64.encode(num.to_le_bytes())), serializer)
+            }
+            (ParamEncoding::Base64, Param::U16(num)) => {
+                Serialize::serialize(&StringParam::U16(b64.encode(num.to_le_bytes())), serializer)
+            }
+            (ParamEncoding::Base64, Param::U8(num)) => {
+                Serialize::serialize(&StringParam::U8(b64.encode([*num])), serializer)
+            }
+            (ParamEncoding::Base64, Param::Char(c)) => {
+                let bytes = u32::from(*c).to_le_bytes();
+                Serialize::serialize(&StringParam::Char(b64.encode(bytes)), serializer)
+            }
+            (ParamEncoding::Base64, Param::String(text)) => {
                 Serialize::serialize(&StringParam::String(b64.encode(text)), serializer)
             }
-            Param::Id(id) => {
-                let num: &usize = id.into();
-                let id = format!("step_{}", num);
+            (ParamEncoding::Base64, Param::Id(id)) => {
+                Serialize::serialize(&StringParam::Id(b64.encode(id.to_string())), serializer)
+            }
+            (ParamEncoding::Base64, Param::ProgramId(id)) => {
                 Serialize::serialize(&StringParam::Id(b64.encode(id)), serializer)
             }
-            Param::Key(key) => Serialize::serialize(key, serializer),
+            (ParamEncoding::Base64, Param::Key(key)) => Serialize::serialize(key, serializer),
+            (ParamEncoding::Base64, param @ Param::Enum { .. }) => {
+                let mut bytes = Vec::new();
+                param
+                    .write_nested(&mut bytes)
+                    .map_err(serde::ser::Error::custom)?;
+                Serialize::serialize(&StringParam::Enum(b64.encode(bytes)), serializer)
+            }
+            (ParamEncoding::Base64, param @ Param::Tuple(_)) => {
+                let mut bytes = Vec::new();
+                param
+                    .write_nested(&mut bytes)
+                    .map_err(serde::ser::Error::custom)?;
+                Serialize::serialize(&StringParam::Tuple(b64.encode(bytes)), serializer)
+            }
+            (ParamEncoding::Base64, Param::Bytes(bytes)) => {
+                Serialize::serialize(&StringParam::Bytes(b64.encode(bytes)), serializer)
+            }
+            (
+                ParamEncoding::Base64,
+                param
+                @ (Param::BytesWithLen { .. } | Param::StringVec(_) | Param::Signature { .. }),
+            ) => {
+                let mut bytes = Vec::new();
+                param
+                    .write_nested(&mut bytes)
+                    .map_err(serde::ser::Error::custom)?;
+                Serialize::serialize(&StringParam::Bytes(b64.encode(bytes)), serializer)
+            }
+        }
+    }
+}
+
+impl Serialize for Param {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.encode(ParamEncoding::default()).serialize(serializer)
+    }
+}
+
+/// Renders the decoded value of the [Param], e.g. `U64(42)` or `Key(ed25519:alice)`.
+impl std::fmt::Display for Param {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Param::U64(num) => write!(f, "U64({num})"),
+            Param::U32(num) => write!(f, "U32({num})"),
+            Param::U16(num) => write!(f, "U16({num})"),
+            Param::U8(num) => write!(f, "U8({num})"),
+            Param::Char(c) => write!(f, "Char({c:?})"),
+            Param::String(text) => write!(f, "String({text:?})"),
+            Param::StringVec(texts) => write!(f, "StringVec({texts:?})"),
+            Param::Id(id) => write!(f, "Id({id})"),
+            Param::ProgramId(id) => write!(f, "ProgramId({})", to_hex(id)),
+            Param::Key(key) => write!(f, "Key({key})"),
+            Param::Timestamp(time) => match time.duration_since(UNIX_EPOCH) {
+                Ok(since_epoch) => write!(f, "Timestamp({})", since_epoch.as_secs()),
+                Err(err) => write!(f, "Timestamp(-{})", err.duration().as_secs()),
+            },
+            Param::Duration(duration) => write!(f, "Duration({}s)", duration.as_secs()),
+            Param::Bytes(bytes) => write!(f, "Bytes({})", to_hex(bytes)),
+            Param::BytesWithLen { bytes, len_prefix } => {
+                write!(f, "BytesWithLen({len_prefix}, {})", to_hex(bytes))
+            }
+            Param::Signature { algorithm, bytes } => {
+                write!(f, "Signature({algorithm}, {})", to_hex(bytes))
+            }
+            Param::Raw {
+                type_tag,
+                value_base64,
+            } => write!(f, "Raw({type_tag}, {value_base64})"),
+            Param::Placeholder(name) => write!(f, "Placeholder({name})"),
+            Param::CallerAddress => write!(f, "CallerAddress"),
+            Param::Enum { variant, fields } => {
+                write!(f, "Enum({variant}, ")?;
+                write_fields(f, fields)?;
+                write!(f, ")")
+            }
+            Param::Tuple(fields) => {
+                write!(f, "Tuple(")?;
+                write_fields(f, fields)?;
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+/// Renders the `fields` of a [Param] as a list, e.g. `[U64(1), String("a")]`.
+fn write_fields(f: &mut std::fmt::Formatter<'_>, fields: &[Param]) -> std::fmt::Result {
+    write!(f, "[")?;
+
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
         }
+        write!(f, "{field}")?;
     }
+
+    write!(f, "]")
 }
 
 impl From<u64> for Param {
@@ -137,6 +995,12 @@ impl From<u64> for Param {
     }
 }
 
+impl From<char> for Param {
+    fn from(val: char) -> Self {
+        Param::Char(val)
+    }
+}
+
 impl From<String> for Param {
     fn from(val: String) -> Self {
         Param::String(val)
@@ -155,32 +1019,343 @@ impl From<Key> for Param {
     }
 }
 
-#[derive(Debug, Serialize, PartialEq)]
+impl From<Vec<String>> for Param {
+    fn from(val: Vec<String>) -> Self {
+        Param::StringVec(val)
+    }
+}
+
+impl From<Vec<u8>> for Param {
+    fn from(val: Vec<u8>) -> Self {
+        Param::Bytes(val)
+    }
+}
+
+impl From<SystemTime> for Param {
+    fn from(val: SystemTime) -> Self {
+        Param::Timestamp(val)
+    }
+}
+
+impl From<Duration> for Param {
+    fn from(val: Duration) -> Self {
+        Param::Duration(val)
+    }
+}
+
+/// The source of the tags telling the [Id]s of different [Plan]s apart.
+static NEXT_PLAN_TAG: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Debug, Serialize)]
 pub struct Plan<'a> {
     /// The key of the caller used in each step of the plan.
     pub caller_key: &'a str,
+    /// An optional human-readable description of the plan. The simulator
+    /// ignores this field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     /// The steps to perform in the plan.
     pub steps: Vec<Step>,
+    /// The labels given to steps with [`Plan::add_labeled_step`]. They are
+    /// not sent to the simulator.
+    #[serde(skip)]
+    pub labels: HashMap<String, Id>,
+    /// The number of params of each method registered with
+    /// [`Plan::register_signature`]. They are not sent to the simulator.
+    #[serde(skip)]
+    pub signatures: HashMap<String, usize>,
+    /// The keys registered with [`Plan::register_existing_key`] as created
+    /// before the plan runs. They are not sent to the simulator.
+    #[serde(skip)]
+    pub existing_keys: HashSet<String>,
+    /// Tags the [Id]s returned by the plan, shared by its clones.
+    #[serde(skip)]
+    tag: u64,
+}
+
+/// Compares everything but the tag of the plans' [Id]s.
+impl PartialEq for Plan<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.caller_key == other.caller_key
+            && self.description == other.description
+            && self.steps == other.steps
+            && self.labels == other.labels
+            && self.signatures == other.signatures
+            && self.existing_keys == other.existing_keys
+    }
 }
 
 impl<'a> Plan<'a> {
     /// Pass in the `caller_key` to be used in each step of the plan.
     #[must_use]
-    pub fn new(caller_key: &'a str) -> Self {
+    pub fn new(caller_key: impl Into<CallerKey<'a>>) -> Self {
         Self {
-            caller_key,
+            caller_key: caller_key.into().as_str(),
+            description: None,
             steps: vec![],
+            labels: HashMap::new(),
+            signatures: HashMap::new(),
+            existing_keys: HashSet::new(),
+            tag: NEXT_PLAN_TAG.fetch_add(1, Ordering::Relaxed),
         }
     }
 
+    /// Creates a [Plan] with a [Step] for each `(endpoint, method, max_units, params)` call.
+    #[must_use]
+    pub fn from_calls<I>(caller_key: impl Into<CallerKey<'a>>, calls: I) -> Self
+    where
+        I: IntoIterator<Item = (Endpoint, String, u64, Vec<Param>)>,
+    {
+        let mut plan = Self::new(caller_key);
+
+        plan.steps = calls
+            .into_iter()
+            .map(|(endpoint, method, max_units, params)| {
+                Step::new(endpoint, method, max_units, params)
+            })
+            .collect();
+
+        plan
+    }
+
     /// returns the [Id] of the added [Step]
     pub fn add_step(&mut self, step: Step) -> Id {
         self.steps.push(step);
-        Id::from(self.steps.len() - 1)
+        self.tagged_id(self.steps.len() - 1)
+    }
+
+    /// Creates a [Plan] with an [`Endpoint::ReadOnly`] call to each of the
+    /// `methods` of the program created by `program_step`, passing the
+    /// program id followed by the given params. Run it after the plan creating
+    /// the program with [`Client::run_plans_sequential`], which rebases
+    /// `program_step` to the step of that plan.
+    #[must_use]
+    pub fn smoke_test(
+        caller_key: impl Into<CallerKey<'a>>,
+        program_step: Id,
+        methods: &[(&str, Vec<Param>)],
+    ) -> Self {
+        let calls = methods.iter().map(|(method, params)| {
+            let params = std::iter::once(Param::Id(program_step))
+                .chain(params.iter().cloned())
+                .collect();
+
+            (Endpoint::ReadOnly, (*method).to_string(), 0, params)
+        });
+
+        Self::from_calls(caller_key, calls)
+    }
+
+    /// Adds a [Step] like [`Plan::add_step`], under a `label` to look up the
+    /// step or its response by, see [`RunOutcome::by_label`]. A label given
+    /// to an earlier step is moved to this one.
+    pub fn add_labeled_step<S: Into<String>>(&mut self, label: S, step: Step) -> Id {
+        let id = self.add_step(step);
+        self.labels.insert(label.into(), id);
+        id
+    }
+
+    /// Returns the [Id] of the step with the `label`.
+    #[must_use]
+    pub fn step_id(&self, label: &str) -> Option<Id> {
+        self.labels.get(label).copied()
+    }
+
+    /// Returns the [Id] of the step at `index`, checking that the [Plan] has
+    /// such a step, unlike [`Id::from`].
+    /// # Errors
+    ///
+    /// Returns an error if `index` is past the last step.
+    pub fn id_for(&self, index: usize) -> Result<Id, PlanError> {
+        if index < self.steps.len() {
+            Ok(self.tagged_id(index))
+        } else {
+            Err(PlanError(format!(
+                "step {index} is out of range for a plan of {} steps",
+                self.steps.len()
+            )))
+        }
+    }
+
+    /// Adds a [`Step::create_key`] for each of the `keys`, returning their [Id]s in order.
+    pub fn create_keys<I>(&mut self, keys: I) -> Vec<Id>
+    where
+        I: IntoIterator<Item = Key>,
+    {
+        keys.into_iter()
+            .map(|key| self.add_step(Step::create_key(key)))
+            .collect()
+    }
+
+    /// Returns the [Key] created by the `step`, or `None` if it is not a
+    /// [`Step::create_key`] step of the [Plan].
+    #[must_use]
+    pub fn created_key(&self, step: Id) -> Option<&Key> {
+        let step = self.steps.get(*<&usize>::from(&step))?;
+
+        match step.params.first() {
+            Some(Param::Key(key)) if step.endpoint == Endpoint::Key => Some(key),
+            _ => None,
+        }
+    }
+
+    /// Removes adjacent identical [`Endpoint::ReadOnly`] and [`Endpoint::Key`]
+    /// [Step]s, keeping the first of each run. Repeated [`Endpoint::Execute`]
+    /// steps are kept, as each of them changes the program state.
+    ///
+    /// A duplicate that is referenced by a [`Param::Id`] or labeled is never
+    /// removed, as that would change which step the reference resolves to.
+    /// References and labels of steps after a removed duplicate are shifted so
+    /// they still point at the same step, while ids beyond the end of the
+    /// [Plan] or returned by another plan, e.g. of steps of an earlier plan of
+    /// the session, are left unchanged.
+    pub fn dedup_consecutive(&mut self) {
+        let referenced: HashSet<usize> = self
+            .steps
+            .iter()
+            .flat_map(Step::referenced_ids)
+            .chain(self.labels.values())
+            .filter(|id| id.belongs_to(self.tag))
+            .map(|id| *<&usize>::from(id))
+            .collect();
+
+        let mut remapped = Vec::with_capacity(self.steps.len());
+        let mut steps: Vec<Step> = Vec::with_capacity(self.steps.len());
+
+        for (index, step) in self.steps.drain(..).enumerate() {
+            let coalesced = matches!(step.endpoint, Endpoint::ReadOnly | Endpoint::Key);
+
+            if coalesced && !referenced.contains(&index) && steps.last() == Some(&step) {
+                remapped.push(steps.len() - 1);
+                continue;
+            }
+
+            remapped.push(steps.len());
+            steps.push(step);
+        }
+
+        self.steps = steps;
+        self.remap_ids(|index| remapped.get(index).copied().unwrap_or(index));
+    }
+
+    /// Replaces every [`Param::Placeholder`] named `name` in the steps of the
+    /// [Plan] with `value`.
+    pub fn bind(&mut self, name: &str, value: Param) {
+        for param in self.steps.iter_mut().flat_map(|step| &mut step.params) {
+            param.bind(name, &value);
+        }
+    }
+
+    /// Inserts a [Step] at `index`, shifting the steps after it. Every
+    /// [`Param::Id`], dependency and label pointing at a shifted step is
+    /// updated so it still points at the same step. Ids returned by another
+    /// plan are left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of steps.
+    pub fn insert_step(&mut self, index: usize, step: Step) -> Id {
+        self.remap_ids(|id| if id >= index { id + 1 } else { id });
+        self.steps.insert(index, step);
+        self.tagged_id(index)
+    }
+
+    /// Removes the step at `index`, shifting the steps after it like
+    /// [`Plan::insert_step`]. Labels of the removed step are dropped.
+    /// # Errors
+    ///
+    /// Returns an error if there is no step at `index`, or if a [`Param::Id`]
+    /// or [dependency](Step::depends_on) references it, in which case the
+    /// [Plan] is left unchanged.
+    pub fn remove_step(&mut self, index: usize) -> Result<Step, RemoveStepError> {
+        if index >= self.steps.len() {
+            return Err(RemoveStepError::NotFound(index));
+        }
+
+        let referencing = self.steps.iter().position(|step| {
+            step.referenced_ids()
+                .any(|id| id.belongs_to(self.tag) && *<&usize>::from(id) == index)
+        });
+
+        if let Some(by) = referencing {
+            return Err(RemoveStepError::Referenced { step: index, by });
+        }
+
+        self.labels.retain(|_, id| *<&usize>::from(&*id) != index);
+        let step = self.steps.remove(index);
+        self.remap_ids(|id| if id > index { id - 1 } else { id });
+
+        Ok(step)
+    }
+
+    /// Rewrites the index of every [`Param::Id`], dependency and label of the
+    /// [Plan] with `remap`, leaving the ids returned by other plans unchanged.
+    fn remap_ids<F: Fn(usize) -> usize>(&mut self, remap: F) {
+        let tag = self.tag;
+
+        for id in self.ids_mut().filter(|id| id.belongs_to(tag)) {
+            let remapped = Id::from(remap(*<&usize>::from(&*id)));
+            *id = match id.plan() {
+                Some(tag) => remapped.with_plan(tag),
+                None => remapped,
+            };
+        }
+    }
+
+    /// Turns the [Id]s returned by the plans with the tags of `offsets` into
+    /// ids of the simulator session, by adding the number of steps run before
+    /// the first step of their plan.
+    fn rebase_ids(&mut self, offsets: &HashMap<u64, usize>) {
+        for id in self.ids_mut() {
+            if let Some(offset) = id.plan().and_then(|tag| offsets.get(&tag)) {
+                *id = Id::from(*<&usize>::from(&*id) + offset);
+            }
+        }
+    }
+
+    fn ids_mut(&mut self) -> impl Iterator<Item = &mut Id> {
+        self.steps
+            .iter_mut()
+            .flat_map(|step| {
+                let params = step.params.iter_mut().filter_map(|param| match param {
+                    Param::Id(id) => Some(id),
+                    _ => None,
+                });
+                params.chain(&mut step.dependencies)
+            })
+            .chain(self.labels.values_mut())
+    }
+
+    fn tagged_id(&self, index: usize) -> Id {
+        Id::from(index).with_plan(self.tag)
+    }
+
+    /// Serializes the steps of the [Plan] as the simulator reads them, one
+    /// JSON object per line with the `callerKey` of the plan. The simulator has
+    /// no mode taking a whole plan: each line is a step to pass to its `run`
+    /// command, with `--step` or in a `--file`.
+    /// # Errors
+    ///
+    /// Returns an error if the serialization fails.
+    pub fn to_batch_json(&self) -> Result<String, StepError> {
+        let mut lines = String::new();
+
+        for step in &self.steps {
+            let step = SimulatorStep {
+                caller_key: self.caller_key,
+                step,
+                encoding: ParamEncoding::default(),
+            };
+
+            lines.push_str(&serde_json::to_string(&step).map_err(StepError::Serde)?);
+            lines.push('\n');
+        }
+
+        Ok(lines)
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, PartialEq, Deserialize)]
 pub struct BaseResponse {
     /// The numeric id of the step.
     pub id: usize,
@@ -188,7 +1363,16 @@ pub struct BaseResponse {
     pub error: Option<PlanError>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// The reason [`Plan::remove_step`] could not remove a step.
+#[derive(Error, Debug, PartialEq)]
+pub enum RemoveStepError {
+    #[error("step {0} is not in the plan")]
+    NotFound(usize),
+    #[error("step {step} is referenced by step {by}")]
+    Referenced { step: usize, by: usize },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct PlanError(String);
 impl std::fmt::Display for PlanError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -196,17 +1380,109 @@ impl std::fmt::Display for PlanError {
     }
 }
 
+/// The result of a [Step].
+///
+/// The simulator does not report which state keys a step changed; inspect
+/// state through the program's own read-only functions instead. Nor does it
+/// report the results of program-to-program calls made during the step: the
+/// `response` only holds the return value of the function the step called.
+/// Likewise there is no call trace or call depth; to check that a program
+/// called another, assert on the callee's state after the step. Log lines and
+/// events a program emits are not reported either: the simulator only writes
+/// them to its own log, so assert on the state they describe.
 #[derive(Debug, Deserialize)]
 pub struct PlanResult {
-    /// The ID created from the program execution.
+    /// The ID created from the program execution. Each execute gets a new
+    /// random transaction id and the simulator keeps no nonce per caller, so
+    /// the same execute can run again without any nonce handling.
     pub id: Option<String>,
-    /// An optional message.
+    /// An optional message. The simulator only sets it for [`Endpoint::Key`]
+    /// steps, to `"created named key with address <address>"`, see
+    /// [`PlanResult::key_address`].
     pub msg: Option<String>,
     /// The timestamp of the function call response.
     pub timestamp: u64,
-    /// The result of the function call.
+    /// The bytes returned by the function call, borsh-encoded by the program.
+    /// Logs are never part of it.
     #[serde(deserialize_with = "base64_decode")]
     pub response: Vec<u8>,
+    /// The units left from the step's `max_units` after an [`Endpoint::Execute`] call.
+    #[serde(default)]
+    pub balance: u64,
+}
+
+const KEY_CREATED_MSG: &str = "created named key with address ";
+
+impl PlanResult {
+    /// Returns the bytes returned by the function call, see [`PlanResult::response`].
+    #[must_use]
+    pub fn return_data(&self) -> &[u8] {
+        &self.response
+    }
+
+    /// Returns the `msg`, if any.
+    #[must_use]
+    pub fn message(&self) -> Option<&str> {
+        self.msg.as_deref()
+    }
+
+    /// Returns the bech32 address of the key created by an [`Endpoint::Key`]
+    /// step, parsed from its `msg`.
+    #[must_use]
+    pub fn key_address(&self) -> Option<&str> {
+        self.message()?.strip_prefix(KEY_CREATED_MSG)
+    }
+
+    /// Returns the `response` as a `0x`-prefixed hex string.
+    #[must_use]
+    pub fn response_hex(&self) -> String {
+        to_hex(&self.response)
+    }
+
+    /// Returns the `response` as a base64 string, as the simulator sent it.
+    #[must_use]
+    pub fn response_base64(&self) -> String {
+        b64.encode(&self.response)
+    }
+
+    /// Returns the `response` as a little-endian `u64`, or `None` if it is
+    /// not exactly 8 bytes.
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        self.response
+            .as_slice()
+            .try_into()
+            .ok()
+            .map(u64::from_le_bytes)
+    }
+
+    /// Returns the `response` as a little-endian `i64`, or `None` if it is
+    /// not exactly 8 bytes.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        self.response
+            .as_slice()
+            .try_into()
+            .ok()
+            .map(i64::from_le_bytes)
+    }
+
+    /// Returns the `response` as a little-endian `u128`, or `None` if it is
+    /// not exactly 16 bytes.
+    #[must_use]
+    pub fn as_u128(&self) -> Option<u128> {
+        self.response
+            .as_slice()
+            .try_into()
+            .ok()
+            .map(u128::from_le_bytes)
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold(String::from("0x"), |hex, byte| hex + &format!("{byte:02x}"))
 }
 
 fn base64_encode<S>(text: &str, serializer: S) -> Result<S::Ok, S::Error>
@@ -239,6 +1515,8 @@ where
     pub timestamp: u64,
     /// The result of the function call.
     pub response: T,
+    /// The units left from the step's `max_units` after an [`Endpoint::Execute`] call.
+    pub balance: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -249,6 +1527,8 @@ pub struct PlanResponse {
     pub result: PlanResult,
 }
 
+/// A [`PlanResponse`] whose response is decoded from borsh as a `T`, e.g. a
+/// tuple such as `(u64, String)` for a program returning several values.
 #[derive(Debug, Deserialize)]
 pub struct PlanResponseTyped<T>
 where
@@ -260,6 +1540,91 @@ where
     pub result: PlanResultTyped<T>,
 }
 
+/// A [`PlanResultTyped`] whose response is decoded from JSON rather than
+/// borsh, for programs returning JSON.
+#[derive(Debug)]
+pub struct PlanResultTypedJson<T>
+where
+    T: DeserializeOwned,
+{
+    /// The ID created from the program execution.
+    pub id: Option<String>,
+    /// An optional message.
+    pub msg: Option<String>,
+    /// The timestamp of the function call response.
+    pub timestamp: u64,
+    /// The result of the function call.
+    pub response: T,
+    /// The units left from the step's `max_units` after an [`Endpoint::Execute`] call.
+    pub balance: u64,
+}
+
+/// A [`PlanResponseTyped`] whose response is decoded from JSON, converted
+/// from a [`PlanResponse`] or returned by [`Client::run_step_json`].
+#[derive(Debug)]
+pub struct PlanResponseTypedJson<T>
+where
+    T: DeserializeOwned,
+{
+    pub base: BaseResponse,
+    /// The result of the plan.
+    pub result: PlanResultTypedJson<T>,
+}
+
+/// The result of a [`Step::create_key`] step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyResult {
+    /// The [Id] of the step, to reference the key with a [`Param::Id`].
+    pub id: Id,
+    /// The bech32 address of the created key, see [`PlanResult::key_address`].
+    pub address: Option<String>,
+}
+
+impl PlanResponse {
+    /// Returns the [`KeyResult`] of a [`Step::create_key`] step.
+    #[must_use]
+    pub fn key_result(&self) -> KeyResult {
+        KeyResult {
+            id: Id::from(self.base.id),
+            address: self.result.key_address().map(String::from),
+        }
+    }
+
+    /// Whether the step succeeded.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.base.error.is_none()
+    }
+
+    /// Returns the error message of a failed step.
+    #[must_use]
+    pub fn error_message(&self) -> Option<String> {
+        self.base.error.as_ref().map(ToString::to_string)
+    }
+
+    /// Decodes the `response` from borsh, or returns `None` if the program
+    /// failed with the [`error`](BaseResponse::error) and returned nothing.
+    /// # Errors
+    ///
+    /// Returns an error if the program succeeded but its response is not the
+    /// borsh encoding of a `T`.
+    pub fn decode<T: BorshDeserialize>(&self) -> Result<Option<T>, borsh::io::Error> {
+        match self.base.error {
+            Some(_) => Ok(None),
+            None => borsh::from_slice(&self.result.response).map(Some),
+        }
+    }
+}
+
+/// Indexes the `responses` of a run by their step id, see [`BaseResponse::id`].
+#[must_use]
+pub fn responses_by_id(responses: Vec<PlanResponse>) -> HashMap<usize, PlanResponse> {
+    responses
+        .into_iter()
+        .map(|resp| (resp.base.id, resp))
+        .collect()
+}
+
 impl<T> TryFrom<PlanResponse> for PlanResponseTyped<T>
 where
     T: BorshDeserialize,
@@ -275,6 +1640,7 @@ where
                     msg,
                     timestamp,
                     response,
+                    balance,
                 },
         } = value;
 
@@ -285,6 +1651,39 @@ where
                 msg,
                 timestamp,
                 response: borsh::from_slice(&response)?,
+                balance,
+            },
+        })
+    }
+}
+
+impl<T> TryFrom<PlanResponse> for PlanResponseTypedJson<T>
+where
+    T: DeserializeOwned,
+{
+    type Error = serde_json::Error;
+
+    fn try_from(value: PlanResponse) -> Result<Self, Self::Error> {
+        let PlanResponse {
+            base,
+            result:
+                PlanResult {
+                    id,
+                    msg,
+                    timestamp,
+                    response,
+                    balance,
+                },
+        } = value;
+
+        Ok(PlanResponseTypedJson {
+            base,
+            result: PlanResultTypedJson {
+                id,
+                msg,
+                timestamp,
+                response: serde_json::from_slice(&response)?,
+                balance,
             },
         })
     }
@@ -298,6 +1697,17 @@ pub enum ClientError {
     Eof,
     #[error("Missing handle")]
     StdIo,
+    /// A blank line where a response was expected. Only returned with
+    /// [`skip_non_json`](ClientBuilder::skip_non_json) disabled: by default
+    /// blank lines are skipped like other non-JSON output.
+    #[error("Empty response line")]
+    EmptyResponse,
+    #[error("No response within {0:?}")]
+    Timeout(Duration),
+    #[error("Simulator binary not found at path: {0}")]
+    NotFound(&'static str),
+    #[error("Response line longer than {0} bytes")]
+    ResponseTooLarge(usize),
 }
 
 #[derive(Error, Debug)]
@@ -308,194 +1718,2683 @@ pub enum StepError {
     Serde(#[from] serde_json::Error),
     #[error("Borsh deserialization error: {0}")]
     BorshDeserialization(#[from] borsh::io::Error),
+    #[error("Plan error: {0}")]
+    Plan(PlanError),
+    #[error("Plan run cancelled")]
+    Cancelled,
+    #[error("Step {0} is not in the plan")]
+    MissingStep(usize),
+    #[error("Step {id} responded differently when the plan was rerun")]
+    Nondeterministic { id: usize },
+    #[error("Program not found at any of {0:?}")]
+    ProgramNotFound(Vec<PathBuf>),
+    #[error("Step {id} succeeded, expected error: {expected}")]
+    UnexpectedSuccess { id: usize, expected: String },
+    #[error("Step {id} failed with: {actual}, expected error: {expected}")]
+    UnexpectedError {
+        id: usize,
+        expected: String,
+        actual: String,
+    },
 }
 
 /// A [Client] is required to pass a [Plan] to the simulator, then to [run](Self::run_plan) the actual simulation.
+///
+/// Every run borrows the [Client] mutably, so a step is always written and
+/// its response read before another step can be sent: runs on one [Client]
+/// never interleave. To share a [Client] between threads, wrap it in a
+/// [`Mutex`](std::sync::Mutex) and hold the lock for the whole run, or use
+/// [`Client::run_plans_sequential`] for plans that depend on each other.
+///
+/// A [Client] is [`Send`] and [`Sync`] whenever its writer and response
+/// iterator are, so a [`SimulatorClient`] can be moved into another thread.
+/// A client built with [`Client::new`] from a non-[`Send`] iterator, e.g.
+/// one capturing an [`Rc`](std::rc::Rc), has to stay on the thread that
+/// created it.
 pub struct Client<W, R> {
+    // dropped first, closing the simulator's stdin before the process waits for it to exit
     writer: W,
     responses: R,
+    encoding: ParamEncoding,
+    #[cfg(feature = "client")]
+    process: Option<Process>,
+    timeout: Option<Duration>,
+    next_timeout: Option<fn(&mut R, Duration) -> Option<StepResult>>,
+}
+
+/// The [Client] spawned by [`ClientBuilder::try_build`].
+#[cfg(feature = "client")]
+pub type SimulatorClient = Client<ChildStdin, ThreadedResponses>;
+
+/// The [Client] returned by [`Client::dry_run`].
+pub type DryRunClient = Client<io::Sink, DryRunResponses>;
+
+/// The simulator subprocess of a [`SimulatorClient`], along with the builder
+/// used to respawn it.
+#[cfg(feature = "client")]
+struct Process {
+    child: Child,
+    builder: ClientBuilder,
+}
+
+/// How long a dropped [Process] waits for the simulator to exit before killing it.
+#[cfg(feature = "client")]
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Waits for the simulator to exit, which it does once its stdin is closed,
+/// so that no process outlives its client, even one dropped while a panic
+/// unwinds. A simulator that does not exit in time is killed.
+#[cfg(feature = "client")]
+impl Drop for Process {
+    fn drop(&mut self) {
+        let deadline = Instant::now() + SHUTDOWN_GRACE;
+
+        while Instant::now() < deadline {
+            match self.child.try_wait() {
+                Ok(None) => std::thread::sleep(Duration::from_millis(10)),
+                Ok(Some(_)) | Err(_) => return,
+            }
+        }
+
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
 }
 
 type StepResult = Result<PlanResponse, StepError>;
 
-pub struct ClientBuilder<'a> {
-    path: &'a str,
+#[cfg(feature = "client")]
+#[derive(Clone)]
+pub struct ClientBuilder {
+    path: &'static str,
+    encoding: ParamEncoding,
+    skip_non_json: bool,
+    current_dir: Option<PathBuf>,
+    read_buffer_size: Option<usize>,
+    read_timeout: Option<Duration>,
+    tee_stdout: Option<PathBuf>,
+    max_response_bytes: Option<usize>,
+    inherit_stdio: bool,
 }
 
-impl ClientBuilder<'_> {
+#[cfg(feature = "client")]
+impl ClientBuilder {
+    /// Creates a builder for the simulator binary this crate was built with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the binary is missing, after printing how to rebuild it.
+    /// Use [`ClientBuilder::try_new`] to handle a missing binary quietly.
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
         let path = env!("SIMULATOR_PATH");
 
-        if !Path::new(path).exists() {
-            eprintln!();
-            eprintln!("Simulator binary not found at path: {path}");
-            eprintln!();
-            eprintln!("Please run `cargo clean -p simulator` and rebuild your dependent crate.");
-            eprintln!();
-
+        if locate(path, true, &mut io::stderr()).is_err() {
             panic!("Simulator binary not found, must rebuild simulator");
         }
 
-        Self { path }
+        Self::with_path(path)
     }
 
-    pub fn try_build(
-        self,
-    ) -> Result<Client<impl Write, impl Iterator<Item = StepResult>>, ClientError> {
-        let Child { stdin, stdout, .. } = Command::new(self.path)
-            .arg("interpreter")
-            .arg("--cleanup")
-            .arg("--log-level")
-            .arg("error")
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()?;
+    /// Creates a builder for the simulator binary this crate was built with,
+    /// without printing anything if it is missing.
+    /// # Errors
+    ///
+    /// Returns [`ClientError::NotFound`] if the binary is missing.
+    pub fn try_new() -> Result<Self, ClientError> {
+        let path = env!("SIMULATOR_PATH");
+        locate(path, false, &mut io::sink())?;
 
-        let writer = stdin.ok_or(ClientError::StdIo)?;
-        let reader = stdout.ok_or(ClientError::StdIo)?;
+        Ok(Self::with_path(path))
+    }
 
-        let responses = BufReader::new(reader)
-            .lines()
-            .map(|line| serde_json::from_str(&line?).map_err(StepError::Serde));
+    fn with_path(path: &'static str) -> Self {
+        Self {
+            path,
+            encoding: ParamEncoding::default(),
+            skip_non_json: true,
+            current_dir: None,
+            read_buffer_size: None,
+            read_timeout: None,
+            tee_stdout: None,
+            max_response_bytes: None,
+            inherit_stdio: false,
+        }
+    }
 
-        Ok(Client { writer, responses })
+    /// Sets the [`ParamEncoding`] used for every [Param] sent to the simulator.
+    #[must_use]
+    pub fn param_encoding(mut self, encoding: ParamEncoding) -> Self {
+        self.encoding = encoding;
+        self
     }
-}
 
-impl<W, R> Client<W, R>
-where
-    W: Write,
-    R: Iterator<Item = StepResult>,
-{
-    /// Runs a [Plan] against the simulator and returns vec of result.
-    /// # Errors
-    ///
-    /// Returns an error if the serialization or plan fails.
-    pub fn run_plan(&mut self, plan: Plan) -> Result<Vec<PlanResponse>, StepError> {
-        plan.steps
-            .iter()
-            .map(|step| self._run_step(plan.caller_key, step))
-            .collect()
+    /// Whether to skip simulator output lines that are not JSON responses,
+    /// such as leaked log output or blank lines. Enabled by default. When
+    /// disabled, a blank line fails with [`ClientError::EmptyResponse`] and
+    /// other lines with a [`StepError::Serde`].
+    #[must_use]
+    pub fn skip_non_json(mut self, skip: bool) -> Self {
+        self.skip_non_json = skip;
+        self
     }
 
-    fn _run_step(&mut self, caller_key: &str, step: &Step) -> Result<PlanResponse, StepError> {
-        let run_command = b"run --step '";
-        self.writer.write_all(run_command)?;
+    /// Sets the working directory of the simulator, against which relative
+    /// paths such as those passed to [`Step::create_program`] are resolved.
+    /// Defaults to the working directory of the current process.
+    #[must_use]
+    pub fn current_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
 
-        let step = SimulatorStep { caller_key, step };
-        let input = serde_json::to_vec(&step).map_err(StepError::Serde)?;
-        self.writer.write_all(&input)?;
-        self.writer.write_all(b"'\n")?;
-        self.writer.flush()?;
+    /// Sets the capacity in bytes of the buffer the simulator's responses are
+    /// read through, e.g. to read plans with large responses in fewer reads.
+    /// Defaults to the [`BufReader`] default.
+    #[must_use]
+    pub fn read_buffer_size(mut self, size: usize) -> Self {
+        self.read_buffer_size = Some(size);
+        self
+    }
 
-        self.responses
-            .next()
-            .ok_or(StepError::Client(ClientError::Eof))?
+    /// Appends everything the simulator writes to its stdout, including
+    /// lines that are not responses, to the file at `path`, e.g. to keep a
+    /// transcript of a failed run. The file is created if it does not exist.
+    #[must_use]
+    pub fn tee_stdout<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.tee_stdout = Some(path.into());
+        self
     }
 
-    pub fn run_step<T>(
-        &mut self,
-        caller_key: &str,
-        step: &Step,
-    ) -> Result<PlanResponseTyped<T>, StepError>
-    where
-        T: BorshDeserialize,
-    {
-        self._run_step(caller_key, step)?
-            .try_into()
-            .map_err(StepError::BorshDeserialization)
+    /// Lets the simulator write to the terminal, for manual debugging only:
+    /// its raw responses are shown as it writes them, but the client cannot
+    /// read them, so every read fails with [`ClientError::Eof`]. Steps are
+    /// still written by the client, e.g. with [`Client::send_plan`].
+    #[must_use]
+    pub fn inherit_stdio(mut self, inherit: bool) -> Self {
+        self.inherit_stdio = inherit;
+        self
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use base64::{engine::general_purpose::STANDARD as b64, Engine};
-    use serde_json::json;
+    /// Fails reading a response line longer than `max` bytes with
+    /// [`ClientError::ResponseTooLarge`], rather than buffering a runaway
+    /// simulator's output whole. Lines are not limited by default.
+    #[must_use]
+    pub fn max_response_bytes(mut self, max: usize) -> Self {
+        self.max_response_bytes = Some(max);
+        self
+    }
 
+    /// Spawns a simulator configured by the builder. The builder is left as
+    /// is, so one builder can spawn any number of identical clients.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the simulator cannot be spawned.
+    pub fn try_build(&self) -> Result<SimulatorClient, ClientError> {
+        let (child, writer, responses) = self.spawn()?;
+
+        Ok(Client {
+            writer,
+            responses,
+            encoding: self.encoding,
+            timeout: self.read_timeout,
+            next_timeout: Some(ThreadedResponses::next_timeout),
+            process: Some(Process {
+                child,
+                builder: self.clone(),
+            }),
+        })
+    }
+
+    /// Sets how long to wait for the response of a step before failing with
+    /// [`ClientError::Timeout`], unless the step sets its own
+    /// [`timeout`](Step::timeout). Waits indefinitely by default.
+    ///
+    /// A timed out step keeps running in the simulator, so
+    /// [restart](Client::restart) the client before running more steps.
+    #[must_use]
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    fn spawn(&self) -> Result<(Child, ChildStdin, ThreadedResponses), ClientError> {
+        let mut command = Command::new(self.path);
+
+        command
+            .arg("interpreter")
+            .arg("--cleanup")
+            .arg("--log-level")
+            .arg("error")
+            .stdin(Stdio::piped());
+
+        if !self.inherit_stdio {
+            command.stdout(Stdio::piped());
+        }
+
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+
+        let mut child = command.spawn()?;
+
+        let writer = child.stdin.take().ok_or(ClientError::StdIo)?;
+
+        let reader: Box<dyn io::Read + Send> = match (child.stdout.take(), &self.tee_stdout) {
+            // the output goes to the terminal, there is nothing to read
+            (None, _) if self.inherit_stdio => Box::new(io::empty()),
+            (None, _) => return Err(ClientError::StdIo),
+            (Some(reader), Some(path)) => {
+                let transcript = File::options().create(true).append(true).open(path)?;
+                Box::new(Tee::new(reader, transcript))
+            }
+            (Some(reader), None) => Box::new(reader),
+        };
+
+        let mut responses = Responses::new(self.buffered(reader)).skip_non_json(self.skip_non_json);
+
+        if let Some(max) = self.max_response_bytes {
+            responses = responses.max_response_bytes(max);
+        }
+
+        Ok((child, writer, ThreadedResponses::spawn(responses)))
+    }
+
+    fn buffered<T: io::Read>(&self, reader: T) -> BufReader<T> {
+        match self.read_buffer_size {
+            Some(size) => BufReader::with_capacity(size, reader),
+            None => BufReader::new(reader),
+        }
+    }
+}
+
+/// Checks the simulator binary exists at `path`, printing how to rebuild it
+/// to `out` if it is missing and `verbose` is set.
+#[cfg(feature = "client")]
+fn locate(path: &'static str, verbose: bool, out: &mut impl Write) -> Result<(), ClientError> {
+    if Path::new(path).exists() {
+        return Ok(());
+    }
+
+    if verbose {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Simulator binary not found at path: {path}");
+        let _ = writeln!(out);
+        let _ = writeln!(
+            out,
+            "Please run `cargo clean -p simulator` and rebuild your dependent crate."
+        );
+        let _ = writeln!(out);
+    }
+
+    Err(ClientError::NotFound(path))
+}
+
+impl<W, R> Client<W, R> {
+    /// Creates a [Client] that writes steps to `writer` and reads their
+    /// responses from `responses`, without a simulator subprocess.
+    #[must_use]
+    pub fn new(writer: W, responses: R) -> Self {
+        Self {
+            writer,
+            responses,
+            encoding: ParamEncoding::default(),
+            #[cfg(feature = "client")]
+            process: None,
+            timeout: None,
+            next_timeout: None,
+        }
+    }
+}
+
+impl<W> Client<W, ThreadedResponses> {
+    /// Sets how long to wait for the response of a step that does not set
+    /// its own [`timeout`](Step::timeout), as [`ClientBuilder::read_timeout`]
+    /// does for a [`SimulatorClient`].
+    #[must_use]
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self.next_timeout = Some(ThreadedResponses::next_timeout);
+        self
+    }
+}
+
+impl DryRunClient {
+    /// Creates a [Client] that serializes every step like a real run, then
+    /// discards it and answers with an empty successful response. This checks
+    /// that a [Plan] can be sent to the simulator without needing its binary.
+    #[must_use]
+    pub fn dry_run() -> Self {
+        Client::new(io::sink(), DryRunResponses::default())
+    }
+}
+
+#[cfg(feature = "client")]
+impl SimulatorClient {
+    /// Checks that the simulator subprocess is still running, and respawns it
+    /// if it exited, e.g. after a step it could not parse. A respawned
+    /// simulator starts from a fresh state: keys, programs and [Id]s from
+    /// earlier runs are gone.
+    /// # Errors
+    ///
+    /// Returns an error if the subprocess cannot be checked or respawned.
+    pub fn ensure_alive(&mut self) -> Result<(), ClientError> {
+        let Some(process) = &mut self.process else {
+            return Ok(());
+        };
+
+        if process.child.try_wait()?.is_none() {
+            return Ok(());
+        }
+
+        self.restart()
+    }
+
+    /// Stops the simulator subprocess and spawns a new one, which starts from
+    /// a fresh state like after [`SimulatorClient::ensure_alive`].
+    /// # Errors
+    ///
+    /// Returns an error if the subprocess cannot be stopped or respawned.
+    pub fn restart(&mut self) -> Result<(), ClientError> {
+        let Some(process) = &mut self.process else {
+            return Ok(());
+        };
+
+        if process.child.try_wait()?.is_none() {
+            process.child.kill()?;
+            process.child.wait()?;
+        }
+
+        let (child, writer, responses) = process.builder.spawn()?;
+
+        process.child = child;
+        self.writer = writer;
+        self.responses = responses;
+
+        Ok(())
+    }
+
+    /// Restarts the simulator, then replays the steps of the `plan` up to and
+    /// including `step_index`, returning the response of that last step. This
+    /// reproduces a failing step with the same state and [Id]s as in the
+    /// original run of the [Plan].
+    /// # Errors
+    ///
+    /// Returns an error if the simulator cannot be restarted, `step_index` is
+    /// not in the `plan`, or a step cannot be run.
+    pub fn rerun_step(
+        &mut self,
+        plan: &Plan,
+        step_index: usize,
+    ) -> Result<PlanResponse, StepError> {
+        self.restart()?;
+        self.replay_until(plan, step_index)
+    }
+
+    /// Runs the `plan` twice, each time in a freshly restarted simulator, and
+    /// checks that every step responds the same both times. Timestamps are
+    /// ignored, and so are the ids of created programs and the addresses of
+    /// created keys, which the simulator generates at random.
+    /// # Errors
+    ///
+    /// Returns [`StepError::Nondeterministic`] for the first step that
+    /// responds differently, or an error if a run fails.
+    pub fn run_plan_twice_assert_deterministic(&mut self, plan: &Plan) -> Result<(), StepError> {
+        self.restart()?;
+        let first: Vec<_> = self.iter_plan(plan).collect::<Result<_, _>>()?;
+
+        self.restart()?;
+        let second: Vec<_> = self.iter_plan(plan).collect::<Result<_, _>>()?;
+
+        assert_same_responses(&first, &second)
+    }
+}
+
+/// Checks the `response` of a step against the error it is expected to fail
+/// with, see [`Step::expect_error`].
+fn check_expected_error(
+    expected: Option<&str>,
+    response: PlanResponse,
+) -> Result<PlanResponse, StepError> {
+    match (expected, &response.base.error) {
+        (None, _) => Ok(response),
+        (Some(expected), None) => Err(StepError::UnexpectedSuccess {
+            id: response.base.id,
+            expected: expected.into(),
+        }),
+        (Some(expected), Some(actual)) if actual.0.contains(expected) => Ok(response),
+        (Some(expected), Some(actual)) => Err(StepError::UnexpectedError {
+            id: response.base.id,
+            expected: expected.into(),
+            actual: actual.to_string(),
+        }),
+    }
+}
+
+/// Checks that two runs of a plan responded the same, see
+/// [`SimulatorClient::run_plan_twice_assert_deterministic`].
+#[cfg(feature = "client")]
+fn assert_same_responses(first: &[PlanResponse], second: &[PlanResponse]) -> Result<(), StepError> {
+    let differs = |(a, b): &(&PlanResponse, &PlanResponse)| {
+        a.base != b.base
+            || a.result.response != b.result.response
+            || a.result.balance != b.result.balance
+    };
+
+    match first.iter().zip(second).find(differs) {
+        Some((response, _)) => Err(StepError::Nondeterministic {
+            id: response.base.id,
+        }),
+        None => Ok(()),
+    }
+}
+
+impl<W, R> Client<W, R>
+where
+    W: Write,
+    R: Iterator<Item = StepResult>,
+{
+    /// Runs a [Plan] against the simulator and returns vec of result.
+    /// # Errors
+    ///
+    /// Returns an error if the serialization or plan fails.
+    pub fn run_plan(&mut self, plan: Plan) -> Result<Vec<PlanResponse>, StepError> {
+        self.iter_plan(&plan).collect()
+    }
+
+    /// Lazily runs a [Plan]: each call to `next` sends one step to the
+    /// simulator and reads its response. Stop iterating to skip the
+    /// remaining steps, e.g. after the first error.
+    pub fn iter_plan<'p>(
+        &'p mut self,
+        plan: &'p Plan<'p>,
+    ) -> impl Iterator<Item = StepResult> + 'p {
+        plan.steps
+            .iter()
+            .map(move |step| self._run_step(plan.caller_key, step))
+    }
+
+    /// Runs the `plans` one after the other in the same simulator session, so
+    /// later plans see the keys and programs created by earlier ones. The
+    /// plans run as if they were one: the [Id]s returned by each plan are
+    /// rebased to follow the steps of the plans before it, so a plan can
+    /// reference its own steps and those of earlier plans with the ids
+    /// [`Plan::add_step`] returned. Ids created with [`Id::from`] are sent
+    /// unchanged, as ids of the session.
+    /// # Errors
+    ///
+    /// Returns the first error, without running the remaining plans.
+    pub fn run_plans_sequential(
+        &mut self,
+        mut plans: Vec<Plan>,
+    ) -> Result<Vec<Vec<PlanResponse>>, StepError> {
+        let mut offsets = HashMap::new();
+        let mut offset = 0;
+
+        for plan in &mut plans {
+            offsets.insert(plan.tag, offset);
+            plan.rebase_ids(&offsets);
+            offset += plan.steps.len();
+        }
+
+        plans.into_iter().map(|plan| self.run_plan(plan)).collect()
+    }
+
+    /// Runs the steps of the `plan` up to and including `upto`, returning
+    /// their responses, e.g. to decode a value the next step depends on. The
+    /// session stays open, so build that step from the decoded value and run
+    /// it with [`Client::run_step`], referencing earlier steps by their [Id].
+    /// # Errors
+    ///
+    /// Returns [`StepError::MissingStep`] if `upto` is not a step of the
+    /// `plan`, or the first error of the steps run.
+    pub fn run_until(&mut self, plan: &Plan, upto: Id) -> Result<Vec<PlanResponse>, StepError> {
+        let index = *<&usize>::from(&upto);
+        let steps = plan
+            .steps
+            .get(..=index)
+            .ok_or(StepError::MissingStep(index))?;
+
+        steps
+            .iter()
+            .map(|step| self._run_step(plan.caller_key, step))
+            .collect()
+    }
+
+    /// Runs a [Plan] like [`Client::run_plan`], checking `cancel` before each
+    /// step. A step already sent to the simulator is not interrupted.
+    /// # Errors
+    ///
+    /// Returns [`StepError::Cancelled`] if `cancel` is set before the last
+    /// step, or an error if the serialization or plan fails.
+    pub fn run_plan_cancelable(
+        &mut self,
+        plan: Plan,
+        cancel: Arc<AtomicBool>,
+    ) -> Result<Vec<PlanResponse>, StepError> {
+        plan.steps
+            .iter()
+            .map(|step| {
+                if cancel.load(Ordering::Relaxed) {
+                    return Err(StepError::Cancelled);
+                }
+
+                self._run_step(plan.caller_key, step)
+            })
+            .collect()
+    }
+
+    /// Runs a [Plan] like [`Client::run_plan`], returning a [RunOutcome] to
+    /// look up the ids of the programs it created by step [Id].
+    /// # Errors
+    ///
+    /// Returns an error if the serialization or plan fails.
+    pub fn run_plan_outcome(&mut self, plan: Plan) -> Result<RunOutcome, StepError> {
+        let responses = self.iter_plan(&plan).collect::<Result<_, _>>()?;
+
+        Ok(RunOutcome::new(&plan, responses))
+    }
+
+    /// Runs a [Plan] like [`Client::run_plan`], returning a [RunReport]
+    /// summarizing the run.
+    /// # Errors
+    ///
+    /// Returns an error if the serialization or plan fails.
+    pub fn run_plan_report(&mut self, plan: Plan) -> Result<RunReport, StepError> {
+        let start = Instant::now();
+        let outcome = self.run_plan_outcome(plan)?;
+
+        Ok(RunReport::new(&outcome, start.elapsed()))
+    }
+
+    /// Runs the steps of the `plan` up to and including `step_index`,
+    /// returning the response of the last one.
+    #[cfg(feature = "client")]
+    fn replay_until(&mut self, plan: &Plan, step_index: usize) -> Result<PlanResponse, StepError> {
+        let (last, steps) = plan
+            .steps
+            .get(..=step_index)
+            .and_then(<[Step]>::split_last)
+            .ok_or(StepError::MissingStep(step_index))?;
+
+        for step in steps {
+            self._run_step(plan.caller_key, step)?;
+        }
+
+        self._run_step(plan.caller_key, last)
+    }
+
+    /// Sends every step of the `plan` to the simulator without reading the
+    /// responses, returning the number of steps sent. Read the responses with
+    /// [`Client::collect_responses`]. The simulator stops reading steps while
+    /// its responses are unread and the pipe between them is full, so keep
+    /// batches to a size whose responses fit in the pipe buffer.
+    /// # Errors
+    ///
+    /// Returns an error if a step cannot be serialized or written.
+    pub fn send_plan(&mut self, plan: &Plan) -> Result<usize, StepError> {
+        for step in &plan.steps {
+            self.write_step(plan.caller_key, step)?;
+        }
+
+        Ok(plan.steps.len())
+    }
+
+    /// Reads the responses of `count` steps sent with [`Client::send_plan`],
+    /// ordered by their [`BaseResponse::id`].
+    /// # Errors
+    ///
+    /// Returns an error if fewer than `count` responses can be read.
+    pub fn collect_responses(&mut self, count: usize) -> Result<Vec<PlanResponse>, StepError> {
+        let mut responses = (0..count)
+            .map(|_| self.read_response())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        responses.sort_by_key(|resp| resp.base.id);
+
+        Ok(responses)
+    }
+
+    fn write_step(&mut self, caller_key: &str, step: &Step) -> Result<(), StepError> {
+        let step = SimulatorStep {
+            caller_key,
+            step,
+            encoding: self.encoding,
+        };
+        // serialize first, so a step that cannot be serialized is not sent at all
+        let input = serde_json::to_vec(&step).map_err(StepError::Serde)?;
+
+        self.write_step_json(&[&input])
+    }
+
+    /// Writes the command running the step serialized as the concatenation
+    /// of `parts`.
+    fn write_step_json(&mut self, parts: &[&[u8]]) -> Result<(), StepError> {
+        let run_command = b"run --step '";
+        self.writer.write_all(run_command)?;
+
+        for part in parts {
+            self.writer.write_all(part)?;
+        }
+
+        self.writer.write_all(b"'\n")?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    fn read_response(&mut self) -> Result<PlanResponse, StepError> {
+        self.read_response_within(None)
+    }
+
+    fn read_response_within(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<PlanResponse, StepError> {
+        let response = match (timeout.or(self.timeout), self.next_timeout) {
+            (Some(timeout), Some(next_timeout)) => next_timeout(&mut self.responses, timeout),
+            _ => self.responses.next(),
+        };
+
+        response.ok_or(StepError::Client(ClientError::Eof))?
+    }
+
+    fn _run_step(&mut self, caller_key: &str, step: &Step) -> Result<PlanResponse, StepError> {
+        self.write_step(caller_key, step)?;
+        let response = self.read_response_within(step.timeout)?;
+
+        check_expected_error(step.expect_error.as_deref(), response)
+    }
+
+    /// Runs a single [Step] without decoding its response.
+    ///
+    /// A program failing, e.g. by reverting or running out of units, is not
+    /// an error of the run: the step's response carries it in
+    /// [`base.error`](BaseResponse::error), see [`PlanResponse::decode`]. A
+    /// [`StepError`] means the simulator could not be talked to or the
+    /// failure did not match [`Step::expect_error`].
+    /// # Errors
+    ///
+    /// Returns an error if the step cannot be run.
+    pub fn run_step_untyped<'k>(
+        &mut self,
+        caller_key: impl Into<CallerKey<'k>>,
+        step: &Step,
+    ) -> Result<PlanResponse, StepError> {
+        self._run_step(caller_key.into().as_str(), step)
+    }
+
+    /// Runs a single [Step], decoding its response as borsh.
+    ///
+    /// A program failing, e.g. by reverting or running out of units, is not
+    /// an error of the run: the step's response carries it in
+    /// [`base.error`](BaseResponse::error), with a `None` response as the
+    /// failed program returned nothing to decode. A [`StepError`] means the
+    /// simulator could not be talked to, the response of a successful program
+    /// could not be decoded or the failure did not match
+    /// [`Step::expect_error`].
+    /// # Errors
+    ///
+    /// Returns an error if the step cannot be run or its result cannot be decoded as `T`.
+    pub fn run_step<'k, T>(
+        &mut self,
+        caller_key: impl Into<CallerKey<'k>>,
+        step: &Step,
+    ) -> Result<PlanResponseTyped<Option<T>>, StepError>
+    where
+        T: BorshDeserialize,
+    {
+        let response = self._run_step(caller_key.into().as_str(), step)?;
+        let decoded = response.decode()?;
+
+        let PlanResponse {
+            base,
+            result:
+                PlanResult {
+                    id,
+                    msg,
+                    timestamp,
+                    balance,
+                    ..
+                },
+        } = response;
+
+        Ok(PlanResponseTyped {
+            base,
+            result: PlanResultTyped {
+                id,
+                msg,
+                timestamp,
+                response: decoded,
+                balance,
+            },
+        })
+    }
+
+    /// Runs a [Step] like [`Client::run_step`], decoding its response as JSON
+    /// rather than borsh. A failing program is carried in the response too,
+    /// with a `None` response.
+    /// # Errors
+    ///
+    /// Returns an error if the step cannot be run or the response of a
+    /// successful program is not the JSON of a `T`.
+    pub fn run_step_json<'k, T>(
+        &mut self,
+        caller_key: impl Into<CallerKey<'k>>,
+        step: &Step,
+    ) -> Result<PlanResponseTypedJson<Option<T>>, StepError>
+    where
+        T: DeserializeOwned,
+    {
+        let PlanResponse {
+            base,
+            result:
+                PlanResult {
+                    id,
+                    msg,
+                    timestamp,
+                    response,
+                    balance,
+                },
+        } = self._run_step(caller_key.into().as_str(), step)?;
+
+        let response = match base.error {
+            Some(_) => None,
+            None => Some(serde_json::from_slice(&response)?),
+        };
+
+        Ok(PlanResponseTypedJson {
+            base,
+            result: PlanResultTypedJson {
+                id,
+                msg,
+                timestamp,
+                response,
+                balance,
+            },
+        })
+    }
+
+    /// Makes a read-only call to `method` and returns the decoded result.
+    ///
+    /// Unlike [`Client::run_step`], a failing program has no response to
+    /// return, so its error is returned as [`StepError::Plan`].
+    /// # Errors
+    ///
+    /// Returns an error if the call fails or its result cannot be decoded as `T`.
+    pub fn read<'k, T>(
+        &mut self,
+        caller_key: impl Into<CallerKey<'k>>,
+        method: &str,
+        params: Vec<Param>,
+    ) -> Result<T, StepError>
+    where
+        T: BorshDeserialize,
+    {
+        let step = Step::new(Endpoint::ReadOnly, method, 0, params);
+
+        self.run_step_response(caller_key.into().as_str(), &step)
+    }
+
+    /// Executes the state-changing `method` in a transaction and returns the decoded result.
+    ///
+    /// The simulator cannot estimate the units of a call without committing
+    /// it, and read-only calls are not metered. To size `max_units`, run the
+    /// call in a separate session first and read its
+    /// [`RunOutcome::units_consumed`].
+    ///
+    /// Like [`Client::read`], a failing program is returned as
+    /// [`StepError::Plan`].
+    /// # Errors
+    ///
+    /// Returns an error if the call fails or its result cannot be decoded as `T`.
+    pub fn execute<'k, T>(
+        &mut self,
+        caller_key: impl Into<CallerKey<'k>>,
+        method: &str,
+        max_units: u64,
+        params: Vec<Param>,
+    ) -> Result<T, StepError>
+    where
+        T: BorshDeserialize,
+    {
+        let step = Step::new(Endpoint::Execute, method, max_units, params);
+
+        self.run_step_response(caller_key.into().as_str(), &step)
+    }
+
+    fn run_step_response<T>(&mut self, caller_key: &str, step: &Step) -> Result<T, StepError>
+    where
+        T: BorshDeserialize,
+    {
+        let PlanResponse { base, result } = self._run_step(caller_key, step)?;
+
+        if let Some(err) = base.error {
+            return Err(StepError::Plan(err));
+        }
+
+        borsh::from_slice(&result.response).map_err(StepError::BorshDeserialization)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD as b64, Engine};
+    use serde_json::json;
+    use std::thread;
+
+    fn plan_response(value: &serde_json::Value) -> PlanResponse {
+        serde_json::from_str(&value.to_string()).unwrap()
+    }
+
+    fn mock_client<I>(responses: I) -> Client<Vec<u8>, I::IntoIter>
+    where
+        I: IntoIterator<Item = StepResult>,
+    {
+        Client::new(Vec::new(), responses.into_iter())
+    }
+
+    #[test]
+    fn convert_u64_param() {
+        let value = 42u64;
+        let expected_param_type = "u64";
+        let expected_value = value.to_le_bytes();
+
+        let expected_json = json!({
+            "type": expected_param_type,
+            "value": &b64.encode(expected_value),
+        });
+
+        let param = Param::from(value);
+        let expected_param = Param::U64(value);
+
+        assert_eq!(param, expected_param);
+
+        let output_json = serde_json::to_value(&param).unwrap();
+
+        assert_eq!(output_json, expected_json);
+    }
+
+    #[test]
+    fn convert_small_int_params() {
+        let params = [
+            (Param::U32(7), "u32", 4),
+            (Param::U16(7), "u16", 2),
+            (Param::U8(7), "u8", 1),
+        ];
+
+        for (param, expected_type, expected_len) in params {
+            let output_json = serde_json::to_value(&param).unwrap();
+            let value = b64.decode(output_json["value"].as_str().unwrap()).unwrap();
+
+            assert_eq!(output_json["type"], expected_type);
+            assert_eq!(value.len(), expected_len, "{param}");
+            assert_eq!(value[0], 7);
+            assert!(value[1..].iter().all(|byte| *byte == 0));
+        }
+
+        assert_eq!(Param::U16(7).to_string(), "U16(7)");
+    }
+
+    #[test]
+    fn convert_char_param() {
+        let param = Param::from('\u{20ac}');
+
+        assert_eq!(param, Param::Char('€'));
+        assert_eq!(param.to_string(), "Char('€')");
+        assert_eq!(
+            serde_json::to_value(&param).unwrap(),
+            json!({ "type": "char", "value": b64.encode([0xac, 0x20, 0x00, 0x00]) })
+        );
+    }
+
+    #[test]
+    fn type_tags_match_serialization() {
+        let params = [
+            (Param::U64(1), "u64"),
+            (Param::U32(1), "u32"),
+            (Param::U16(1), "u16"),
+            (Param::U8(1), "u8"),
+            (Param::Char('a'), "char"),
+            (Param::String("a".into()), "string"),
+            (Param::Id(Id::from(0)), "id"),
+            (Param::Key(Key::Ed25519("a".into())), "ed25519"),
+            (Param::Key(Key::Secp256r1("a".into())), "secp256r1"),
+            (Param::Timestamp(UNIX_EPOCH), "u64"),
+            (Param::Duration(Duration::from_secs(1)), "u64"),
+            (
+                Param::Enum {
+                    variant: 0,
+                    fields: vec![],
+                },
+                "enum",
+            ),
+            (Param::Bytes(vec![1]), "bytes"),
+            (
+                Param::Signature {
+                    algorithm: SignatureKind::Ed25519,
+                    bytes: vec![0; 64],
+                },
+                "bytes",
+            ),
+        ];
+
+        for (param, tag) in params {
+            assert_eq!(param.type_tag(), tag);
+            assert_eq!(
+                serde_json::to_value(&param).unwrap()["type"],
+                tag,
+                "{param}"
+            );
+        }
+    }
+
+    #[test]
+    fn convert_raw_param() {
+        let param = Param::Raw {
+            type_tag: "u128".into(),
+            value_base64: "AQAAAAAAAAAAAAAAAAAAAA==".into(),
+        };
+        let expected = json!({ "type": "u128", "value": "AQAAAAAAAAAAAAAAAAAAAA==" });
+
+        assert_eq!(param.type_tag(), "u128");
+        assert_eq!(serde_json::to_value(&param).unwrap(), expected);
+    }
+
+    #[test]
+    fn convert_string_param() {
+        let value = String::from("hello world");
+        let expected_param_type = "string";
+        let expected_value = value.clone();
+
+        let expected_json = json!({
+            "type": expected_param_type,
+            "value": &b64.encode(expected_value),
+        });
+
+        let param = Param::from(value.clone());
+        let expected_param = Param::String(value);
+
+        assert_eq!(param, expected_param);
+
+        let output_json = serde_json::to_value(&param).unwrap();
+
+        assert_eq!(output_json, expected_json);
+    }
+
+    #[test]
+    fn convert_id_param() {
+        let value = 42;
+        let expected_param_type = "id";
+        let expected_value = format!("step_{value}");
+
+        let expected_json = json!({
+            "type": expected_param_type,
+            "value": &b64.encode(expected_value),
+        });
+
+        let id = Id::from(value);
+        let param = Param::from(id);
+        let expected_param = Param::Id(id);
+
+        assert_eq!(param, expected_param);
+
+        let output_json = serde_json::to_value(&param).unwrap();
+
+        assert_eq!(output_json, expected_json);
+    }
+
+    #[test]
+    fn convert_key_param() {
+        let expected_param_type = "ed25519";
+        let expected_value = "id";
+
+        let expected_json = json!({
+            "type": expected_param_type,
+            "value": &b64.encode(expected_value),
+        });
+
+        let key = Key::Ed25519(expected_value.to_string());
+        let param = Param::from(key.clone());
+        let expected_param = Param::Key(key);
+
+        assert_eq!(param, expected_param);
+
+        let output_json = serde_json::to_value(&param).unwrap();
+
+        assert_eq!(output_json, expected_json);
+    }
+
+    #[test]
+    fn dedup_keeps_referenced_steps() {
+        let read = |id: Id| Step::new(Endpoint::ReadOnly, "get_value", 0, vec![id.into()]);
+
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::create_program("counter.wasm"));
+        let counter = plan.add_step(Step::create_program("counter.wasm"));
+        plan.add_step(read(counter));
+        plan.add_step(read(counter));
+        let token = plan.add_step(Step::create_program("token.wasm"));
+        plan.add_step(read(token));
+
+        plan.dedup_consecutive();
+
+        let expected = vec![
+            Step::create_program("counter.wasm"),
+            Step::create_program("counter.wasm"),
+            read(Id::from(1)),
+            Step::create_program("token.wasm"),
+            read(Id::from(3)),
+        ];
+
+        assert_eq!(plan.steps, expected);
+    }
+
+    #[test]
+    fn plan_batch_json() {
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::create_key(Key::Ed25519("alice".into())));
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "inc",
+            1000,
+            vec![Id::from(0).into(), 42u64.into()],
+        ));
+
+        let expected_json = [
+            json!({
+                "callerKey": "alice",
+                "endpoint": "key",
+                "method": "create_key",
+                "maxUnits": 0,
+                "params": [{ "type": "ed25519", "value": b64.encode("alice") }],
+            }),
+            json!({
+                "callerKey": "alice",
+                "endpoint": "execute",
+                "method": "inc",
+                "maxUnits": 1000,
+                "params": [
+                    { "type": "id", "value": b64.encode("step_0") },
+                    { "type": "u64", "value": b64.encode(42u64.to_le_bytes()) },
+                ],
+            }),
+        ];
+
+        let output = plan.to_batch_json().unwrap();
+        let output_json: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(output_json, expected_json);
+
+        // the same lines the client writes for each step
+        let mut client = mock_client((0..2).map(|id| {
+            Ok(plan_response(&json!({
+                "id": id,
+                "result": { "response": "", "timestamp": 0 },
+            })))
+        }));
+        client.run_plan(plan).unwrap();
+
+        let input = String::from_utf8(client.writer).unwrap();
+        let written: Vec<_> = input
+            .lines()
+            .map(|line| {
+                line.strip_prefix("run --step '")
+                    .unwrap()
+                    .trim_end_matches('\'')
+            })
+            .collect();
+        assert_eq!(written, output.lines().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn descriptions_are_serialized_when_present() {
+        let mut plan = Plan::new("alice");
+        plan.description = Some("counter smoke test".into());
+        plan.add_step(
+            Step::create_key(Key::Ed25519("alice".into())).with_description("create the caller"),
+        );
+        plan.add_step(Step::create_program("counter.wasm"));
+
+        let output_json = serde_json::to_value(&plan).unwrap();
+
+        assert_eq!(output_json["description"], "counter smoke test");
+        assert_eq!(output_json["steps"][0]["description"], "create the caller");
+        assert!(output_json["steps"][1].get("description").is_none());
+
+        let step = SimulatorStep {
+            caller_key: "alice",
+            step: &plan.steps[0]
+                .clone()
+                .with_description("create the caller's key"),
+            encoding: ParamEncoding::default(),
+        };
+        let output_json = serde_json::to_value(step).unwrap();
+
+        assert!(output_json.get("description").is_none());
+    }
+
+    #[test]
+    fn read_decodes_response() {
+        let response = json!({
+            "id": 0,
+            "result": { "response": b64.encode(10u64.to_le_bytes()), "timestamp": 0 },
+        });
+        let responses = vec![Ok(plan_response(&response))];
+        let mut client = mock_client(responses);
+
+        let value: u64 = client
+            .read("alice", "get_value", vec![Id::from(0).into()])
+            .unwrap();
+
+        assert_eq!(value, 10);
+
+        let input = String::from_utf8(client.writer).unwrap();
+        assert!(input.contains(r#""endpoint":"readonly""#));
+        assert!(input.contains(r#""method":"get_value""#));
+    }
+
+    #[test]
+    fn execute_then_read() {
+        let responses = [
+            json!({ "id": 0, "result": { "response": "", "timestamp": 0 } }),
+            json!({
+                "id": 1,
+                "result": { "response": b64.encode(1u64.to_le_bytes()), "timestamp": 0 },
+            }),
+        ];
+        let mut client = mock_client(responses.iter().map(|resp| Ok(plan_response(resp))));
+        let params = vec![Param::Id(Id::from(0))];
+
+        client
+            .execute::<()>("alice", "inc", 1_000_000, params.clone())
+            .unwrap();
+        let value: u64 = client.read("alice", "get_value", params).unwrap();
+
+        assert_eq!(value, 1);
+
+        let input = String::from_utf8(client.writer).unwrap();
+        let mut lines = input.lines();
+        assert!(lines.next().unwrap().contains(r#""endpoint":"execute""#));
+        assert!(lines.next().unwrap().contains(r#""endpoint":"readonly""#));
+    }
+
+    #[test]
+    fn outcome_program_ids() {
+        // the plan runs after four steps of an earlier plan of the session
+        let responses = [
+            json!({ "id": 4, "result": { "response": "", "timestamp": 0 } }),
+            json!({ "id": 5, "result": { "id": "first", "response": "", "timestamp": 0 } }),
+            json!({ "id": 6, "result": { "id": "second", "response": "", "timestamp": 0 } }),
+        ];
+        let mut client = mock_client(responses.iter().map(|resp| Ok(plan_response(resp))));
+
+        let mut plan = Plan::new("alice");
+        let key = plan.add_step(Step::create_key(Key::Ed25519("alice".into())));
+        let first = plan.add_step(Step::create_program("first.wasm"));
+        let second = plan.add_step(Step::create_program("second.wasm"));
+
+        let outcome = client.run_plan_outcome(plan).unwrap();
+
+        assert_eq!(outcome.program_id(first), Some("first"));
+        assert_eq!(outcome.program_id(second), Some("second"));
+        assert_eq!(outcome.program_id(key), None);
+        assert_eq!(outcome.program_id(Id::from(3)), None);
+        assert_eq!(outcome.responses().len(), 3);
+    }
+
+    #[test]
+    fn outcome_by_step() {
+        let responses = [
+            json!({ "id": 0, "result": { "response": "", "timestamp": 0 } }),
+            json!({ "id": 1, "error": "out of gas", "result": { "response": "", "timestamp": 0 } }),
+        ];
+        let mut client = mock_client(responses.iter().map(|resp| Ok(plan_response(resp))));
+
+        let mut plan = Plan::new("alice");
+        let keys = plan.create_keys(["alice", "bob"].map(|name| Key::Ed25519(name.into())));
+
+        let outcome = client.run_plan_outcome(plan).unwrap();
+
+        assert!(outcome.get(keys[0]).unwrap().is_success());
+        assert_eq!(
+            outcome.get(keys[1]).unwrap().error_message().unwrap(),
+            "out of gas"
+        );
+        assert!(outcome.get(Id::from(2)).is_none());
+        assert_eq!(outcome.iter().map(|(id, _)| id).collect::<Vec<_>>(), keys);
+    }
+
+    #[test]
+    fn outcome_units() {
+        let responses = [
+            json!({ "id": 0, "result": { "id": "counter", "response": "", "timestamp": 0 } }),
+            json!({ "id": 1, "result": { "response": "", "timestamp": 0, "balance": 750 } }),
+            json!({ "id": 2, "result": { "response": "", "timestamp": 0 } }),
+            json!({ "id": 3, "result": { "response": "", "timestamp": 0, "balance": 4_000 } }),
+        ];
+        let mut client = mock_client(responses.iter().map(|resp| Ok(plan_response(resp))));
+
+        let call =
+            |endpoint, max_units| Step::new(endpoint, "inc", max_units, vec![Id::from(0).into()]);
+
+        let mut plan = Plan::new("alice");
+        let program = plan.add_step(Step::create_program("counter.wasm"));
+        let first = plan.add_step(call(Endpoint::Execute, 1_000));
+        plan.add_step(call(Endpoint::ReadOnly, 0));
+        plan.add_step(call(Endpoint::Execute, 5_000));
+
+        let outcome = client.run_plan_outcome(plan).unwrap();
+
+        assert_eq!(outcome.units_consumed(first), Some(250));
+        assert_eq!(outcome.units_consumed(program), None);
+        assert_eq!(outcome.total_units(), 1_250);
+    }
+
+    #[test]
+    fn run_report() {
+        let responses = [
+            json!({ "id": 0, "result": { "id": "counter", "response": "", "timestamp": 0 } }),
+            json!({ "id": 1, "result": { "response": "", "timestamp": 0, "balance": 600 } }),
+            json!({ "id": 2, "error": "out of gas", "result": { "response": "", "timestamp": 0 } }),
+        ];
+        let mut client = mock_client(responses.iter().map(|resp| Ok(plan_response(resp))));
+
+        let inc = |max_units| {
+            Step::new(
+                Endpoint::Execute,
+                "inc",
+                max_units,
+                vec![Id::from(0).into()],
+            )
+        };
+
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::create_program("counter.wasm"));
+        plan.add_step(inc(1_000));
+        plan.add_step(inc(10));
+
+        let report = client.run_plan_report(plan).unwrap();
+
+        assert_eq!(
+            (
+                report.steps,
+                report.succeeded,
+                report.failed,
+                report.total_units
+            ),
+            (3, 2, 1, 400)
+        );
+        assert!(report
+            .to_string()
+            .starts_with("3 steps: 2 succeeded, 1 failed, 400 units in "));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[cfg(feature = "client")]
+    #[test]
+    fn dropped_client_stops_process() {
+        let mut child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+
+        let writer = child.stdin.take().unwrap();
+        let reader = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Client::new(writer, ThreadedResponses::spawn(Responses::new(reader)));
+        client.process = Some(Process {
+            child,
+            builder: ClientBuilder::with_path("cat"),
+        });
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            let _client = client;
+            panic!("test failed mid-plan");
+        }));
+
+        assert!(result.is_err());
+        // the process exited and was reaped
+        assert!(!Path::new(&format!("/proc/{pid}")).exists());
+    }
+
+    #[test]
+    fn cancel_between_steps() {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let response = json!({ "id": 0, "result": { "response": "", "timestamp": 0 } });
+        let responses = std::iter::repeat_with(|| {
+            cancel.store(true, Ordering::Relaxed);
+            Ok(plan_response(&response))
+        });
+        let mut client = mock_client(responses);
+
+        let mut plan = Plan::new("alice");
+        plan.create_keys([Key::Ed25519("alice".into()), Key::Ed25519("bob".into())]);
+
+        let err = client
+            .run_plan_cancelable(plan, Arc::clone(&cancel))
+            .unwrap_err();
+
+        assert!(matches!(err, StepError::Cancelled));
+        assert_eq!(String::from_utf8(client.writer).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn key_as_caller() {
+        let alice = Key::Ed25519("alice".into());
+        let plan = Plan::new(&alice);
+
+        assert_eq!(plan.caller_key, "alice");
+
+        let response = json!({ "id": 0, "result": { "response": "", "timestamp": 0 } });
+        let mut client = mock_client([Ok(plan_response(&response))]);
+
+        client
+            .execute::<()>(&alice, "inc", 1000, vec![Id::from(0).into()])
+            .unwrap();
+
+        let input = String::from_utf8(client.writer).unwrap();
+        assert!(input.contains(r#""callerKey":"alice""#));
+    }
+
+    #[test]
+    fn run_dependent_plans() {
+        let responses = (0..5).map(|id| {
+            Ok(plan_response(&json!({
+                "id": id,
+                "result": { "response": "", "timestamp": 0 },
+            })))
+        });
+        let mut client = mock_client(responses);
+
+        let mut deploy = Plan::new("alice");
+        deploy.add_step(Step::create_key(Key::Ed25519("alice".into())));
+        let program = deploy.add_step(Step::create_program("counter.wasm"));
+
+        let inc = |program: Id| Step::new(Endpoint::Execute, "inc", 1000, vec![program.into()]);
+
+        let mut call = Plan::new("alice");
+        call.add_step(inc(program));
+        let other = call.add_step(Step::create_program("counter.wasm"));
+        call.add_step(inc(other));
+
+        let responses = client.run_plans_sequential(vec![deploy, call]).unwrap();
+
+        let ids: Vec<Vec<_>> = responses
+            .iter()
+            .map(|plan| plan.iter().map(|resp| resp.base.id).collect())
+            .collect();
+        assert_eq!(ids, [vec![0, 1], vec![2, 3, 4]]);
+
+        let input = String::from_utf8(client.writer).unwrap();
+        let lines: Vec<_> = input.lines().collect();
+        let program = |line: &str| {
+            let json: serde_json::Value = serde_json::from_str(
+                line.strip_prefix("run --step '")
+                    .unwrap()
+                    .trim_end_matches('\''),
+            )
+            .unwrap();
+            json["params"][0]["value"].clone()
+        };
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(program(lines[2]), json!(b64.encode("step_1")));
+        assert_eq!(program(lines[4]), json!(b64.encode("step_3")));
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn locate_missing_binary() {
+        let path = "/nonexistent/simulator";
+
+        let mut out = Vec::new();
+        assert!(matches!(
+            locate(path, false, &mut out),
+            Err(ClientError::NotFound(p)) if p == path
+        ));
+        assert!(out.is_empty());
+
+        assert!(locate(path, true, &mut out).is_err());
+        assert!(String::from_utf8(out).unwrap().contains(path));
+    }
+
+    #[test]
+    fn run_until_then_chain() {
+        let responses = [
+            json!({ "id": 0, "result": { "id": "step_0", "response": "", "timestamp": 0 } }),
+            // a borsh encoded 5u64
+            json!({ "id": 1, "result": { "response": "BQAAAAAAAAA=", "timestamp": 0 } }),
+            json!({ "id": 2, "result": { "response": "", "timestamp": 0 } }),
+        ];
+        let mut client = mock_client(responses.iter().map(|resp| Ok(plan_response(resp))));
+
+        let mut plan = Plan::new("alice");
+        let program = plan.add_step(Step::create_program("counter.wasm"));
+        plan.add_step(Step::new(
+            Endpoint::ReadOnly,
+            "get_value",
+            0,
+            vec![program.into()],
+        ));
+        plan.add_step(Step::create_key(Key::Ed25519("bob".into())));
+
+        let mut responses = client.run_until(&plan, Id::from(1)).unwrap();
+        assert_eq!(responses.len(), 2);
+
+        let value: PlanResponseTyped<u64> = responses.pop().unwrap().try_into().unwrap();
+        assert_eq!(value.result.response, 5);
+
+        client
+            .execute::<()>(
+                "alice",
+                "set_value",
+                1000,
+                vec![program.into(), (value.result.response + 1).into()],
+            )
+            .unwrap();
+
+        let input = String::from_utf8(client.writer).unwrap();
+        assert_eq!(input.lines().count(), 3);
+        assert!(input
+            .lines()
+            .last()
+            .unwrap()
+            .contains(r#""method":"set_value""#));
+
+        let mut client = mock_client([]);
+        assert!(matches!(
+            client.run_until(&plan, Id::from(3)),
+            Err(StepError::MissingStep(3))
+        ));
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn replay_until_step() {
+        let responses = [
+            json!({ "id": 0, "result": { "response": "", "timestamp": 0 } }),
+            json!({ "id": 1, "error": "out of gas", "result": { "response": "", "timestamp": 0 } }),
+        ];
+        let mut client = mock_client(responses.iter().map(|resp| Ok(plan_response(resp))));
+
+        let mut plan = Plan::new("alice");
+        plan.create_keys(["alice", "bob", "carol"].map(|name| Key::Ed25519(name.into())));
+
+        let response = client.replay_until(&plan, 1).unwrap();
+
+        assert_eq!(response.base.id, 1);
+        assert_eq!(response.base.error.unwrap().to_string(), "out of gas");
+        assert_eq!(String::from_utf8(client.writer).unwrap().lines().count(), 2);
+
+        let mut client = mock_client([]);
+        assert!(matches!(
+            client.replay_until(&plan, 3),
+            Err(StepError::MissingStep(3))
+        ));
+    }
+
+    #[test]
+    fn dry_run_serializes_steps() {
+        let mut client = Client::dry_run();
+
+        let mut plan = Plan::new("alice");
+        let key = plan.add_step(Step::create_key(Key::Ed25519("alice".into())));
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "transfer",
+            1000,
+            vec![
+                key.into(),
+                Param::from(SystemTime::UNIX_EPOCH),
+                vec![1, 2].into(),
+            ],
+        ));
+
+        let responses = client.run_plan(plan).unwrap();
+        let ids: Vec<_> = responses.iter().map(|resp| resp.base.id).collect();
+        assert_eq!(ids, [0, 1]);
+
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "vote",
+            1000,
+            vec![Param::Enum {
+                variant: 0,
+                fields: vec![key.into()],
+            }],
+        ));
+
+        assert!(matches!(client.run_plan(plan), Err(StepError::Serde(_))));
+    }
+
+    #[test]
+    fn iter_plan_is_lazy() {
+        let response = json!({ "id": 0, "result": { "response": "", "timestamp": 0 } });
+        let mut client = mock_client(std::iter::repeat_with(|| Ok(plan_response(&response))));
+
+        let mut plan = Plan::new("alice");
+        plan.create_keys(["alice", "bob", "carol"].map(|name| Key::Ed25519(name.into())));
+
+        let mut responses = client.iter_plan(&plan);
+        assert!(responses.next().unwrap().is_ok());
+        drop(responses);
+
+        let input = String::from_utf8(client.writer).unwrap();
+        assert_eq!(input.lines().count(), 1);
+        assert!(input.contains(r#""method":"create_key""#));
+    }
+
+    #[test]
+    fn reuse_created_key() {
+        let mut plan = Plan::new("alice");
+        let [alice, bob] =
+            plan.create_keys([Key::Ed25519("alice".into()), Key::Secp256r1("bob".into())])[..]
+        else {
+            unreachable!()
+        };
+        let program = plan.add_step(Step::create_program("token.wasm"));
+
+        let bob = plan.created_key(bob).unwrap().clone();
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "transfer",
+            1000,
+            vec![program.into(), bob.into()],
+        ));
+
+        assert_eq!(plan.created_key(alice), Some(&Key::Ed25519("alice".into())));
+        assert_eq!(
+            plan.steps[3].params[1],
+            Param::Key(Key::Secp256r1("bob".into()))
+        );
+        assert_eq!(plan.created_key(program), None);
+        assert_eq!(plan.created_key(Id::from(4)), None);
+    }
+
+    #[test]
+    fn clients_are_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        #[cfg(feature = "client")]
+        assert_send_sync::<SimulatorClient>();
+        assert_send_sync::<DryRunClient>();
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn compares_rerun_responses() {
+        let run = |key_address: &str, value: &[u8]| {
+            [
+                plan_response(&json!({
+                    "id": 0,
+                    "result": { "msg": key_address, "response": "", "timestamp": 1 },
+                })),
+                plan_response(&json!({
+                    "id": 1,
+                    "result": { "response": b64.encode(value), "timestamp": 2 },
+                })),
+            ]
+        };
+
+        assert!(assert_same_responses(&run("matrix1a", &[1]), &run("matrix1b", &[1])).is_ok());
+        assert!(matches!(
+            assert_same_responses(&run("matrix1a", &[1]), &run("matrix1a", &[2])),
+            Err(StepError::Nondeterministic { id: 1 })
+        ));
+    }
+
+    #[test]
+    fn step_with_budget() {
+        let step = Step::create_program("counter.wasm");
+
+        assert_eq!(step.clone().with_budget(3, 100).max_units, 33);
+        assert_eq!(step.clone().with_budget(1, 100).max_units, 100);
+        assert_eq!(step.with_budget(200, 100).max_units, 0);
+    }
+
+    #[test]
+    fn step_with_optional_params() {
+        let step = |memo: Option<&str>| {
+            Step::new(
+                Endpoint::Execute,
+                "transfer",
+                1000,
+                vec![Id::from(0).into()],
+            )
+            .with_optional_params([Some(10u64.into()), memo.map(|memo| memo.to_string().into())])
+        };
+
+        assert_eq!(step(None).params, [Id::from(0).into(), Param::U64(10)]);
+        assert_eq!(
+            step(Some("rent")).params,
+            [
+                Id::from(0).into(),
+                Param::U64(10),
+                Param::String("rent".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn step_to_json() {
+        let step = Step::new(
+            Endpoint::Execute,
+            "inc",
+            1000,
+            vec![Id::from(0).into(), 5u64.into()],
+        );
+
+        let json = step.to_json().unwrap();
+
+        assert!(json.contains(r#""method":"inc""#));
+        assert!(json.contains(r#""maxUnits":1000"#));
+        assert!(json.contains(&b64.encode("step_0")));
+        assert!(!json.contains("callerKey"));
+    }
+
+    #[test]
+    fn outcome_by_label() {
+        let responses = (0..3).map(|id| {
+            Ok(plan_response(&json!({
+                "id": id,
+                "result": { "id": format!("program_{id}"), "response": "", "timestamp": 0 },
+            })))
+        });
+        let mut client = mock_client(responses);
+
+        let mut plan = Plan::new("alice");
+        plan.add_labeled_step("alice", Step::create_key(Key::Ed25519("alice".into())));
+        plan.add_step(Step::create_program("token.wasm"));
+        let counter = plan.add_labeled_step("counter", Step::create_program("counter.wasm"));
+
+        assert_eq!(plan.step_id("counter"), Some(counter));
+
+        let outcome = client.run_plan_outcome(plan).unwrap();
+
+        assert_eq!(outcome.by_label("alice").unwrap().base.id, 0);
+        assert_eq!(
+            outcome.by_label("counter").unwrap().result.id.as_deref(),
+            Some("program_2")
+        );
+        assert!(outcome.by_label("token").is_none());
+    }
+
+    #[test]
+    fn dedup_remaps_labels() {
+        let mut plan = Plan::new("alice");
+        let key = Step::create_key(Key::Ed25519("alice".into()));
+
+        plan.add_step(key.clone());
+        plan.add_step(key.clone());
+        plan.add_labeled_step("bob", Step::create_key(Key::Ed25519("bob".into())));
+        plan.add_labeled_step("bob again", Step::create_key(Key::Ed25519("bob".into())));
+
+        plan.dedup_consecutive();
+
+        assert_eq!(plan.steps.len(), 3);
+        assert_eq!(plan.step_id("bob"), Some(Id::from(1)));
+        assert_eq!(plan.step_id("bob again"), Some(Id::from(2)));
+    }
+
+    #[test]
+    fn dedup_keeps_out_of_range_ids() {
+        let read = Step::new(Endpoint::ReadOnly, "get_value", 0, vec![Id::from(7).into()]);
+
+        let mut plan = Plan::new("alice");
+        plan.add_step(read.clone());
+        plan.add_step(read.clone());
+
+        plan.dedup_consecutive();
+
+        assert_eq!(plan.steps, [read]);
+    }
+
+    #[test]
+    fn dedup_keeps_repeated_executes() {
+        let inc = Step::new(Endpoint::Execute, "inc", 1000, vec![Id::from(0).into()]);
+
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::create_program("counter.wasm"));
+        plan.add_step(inc.clone());
+        plan.add_step(inc.clone());
+
+        plan.dedup_consecutive();
+
+        assert_eq!(plan.steps.len(), 3);
+    }
+
+    #[test]
+    fn smoke_test_plan() {
+        let program = Id::from(1);
+        let bob = Param::Key(Key::Ed25519("bob".into()));
+        let plan = Plan::smoke_test(
+            "alice",
+            program,
+            &[
+                ("get_value", vec![bob.clone()]),
+                ("total_supply", vec![]),
+                ("balance_of", vec![bob.clone()]),
+            ],
+        );
+
+        assert_eq!(plan.steps.len(), 3);
+        assert!(plan
+            .steps
+            .iter()
+            .all(|step| step.endpoint == Endpoint::ReadOnly && step.params[0] == program.into()));
+        assert_eq!(plan.steps[1].method, "total_supply");
+        assert_eq!(plan.steps[2].params, [program.into(), bob]);
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn read_buffer_size() {
+        let builder = ClientBuilder::with_path("simulator");
+
+        let default = builder.buffered(io::empty()).capacity();
+        let builder = builder.read_buffer_size(default * 4);
+
+        assert_eq!(builder.buffered(io::empty()).capacity(), default * 4);
+    }
+
+    #[cfg(all(feature = "client", unix))]
     #[test]
-    fn convert_u64_param() {
-        let value = 42u64;
-        let expected_param_type = "u64";
-        let expected_value = value.to_le_bytes();
+    fn inherited_stdio_is_not_read() {
+        // `sh interpreter ...` reads the script named after the first argument
+        // rather than executing it, as a concurrent fork may still hold the
+        // written file open
+        let dir =
+            std::env::temp_dir().join(format!("simulator-inherit-stdio-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("interpreter"), "cat > /dev/null\n").unwrap();
 
-        let expected_json = json!({
-            "type": expected_param_type,
-            "value": &b64.encode(expected_value),
-        });
+        let mut client = ClientBuilder::with_path("sh")
+            .current_dir(&dir)
+            .inherit_stdio(true)
+            .try_build()
+            .unwrap();
 
-        let param = Param::from(value);
-        let expected_param = Param::U64(value);
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::create_key(Key::Ed25519("alice".into())));
 
-        assert_eq!(param, expected_param);
+        assert_eq!(client.send_plan(&plan).unwrap(), 1);
+        assert!(matches!(
+            client.collect_responses(1),
+            Err(StepError::Client(ClientError::Eof))
+        ));
 
-        let output_json = serde_json::to_value(&param).unwrap();
+        drop(client);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 
-        assert_eq!(output_json, expected_json);
+    #[test]
+    fn insert_step_remaps_ids() {
+        let mut plan = Plan::new("alice");
+        let key = plan.add_step(Step::create_key(Key::Ed25519("alice".into())));
+        let program = plan.add_labeled_step("counter", Step::create_program("counter.wasm"));
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "inc",
+            1000,
+            vec![program.into(), key.into()],
+        ));
+
+        let bob = plan.insert_step(1, Step::create_key(Key::Ed25519("bob".into())));
+
+        assert_eq!(bob, Id::from(1));
+        assert_eq!(plan.steps[1], Step::create_key(Key::Ed25519("bob".into())));
+        assert_eq!(plan.steps[3].params, [Id::from(2).into(), key.into()]);
+        assert_eq!(plan.steps[2].method, PROGRAM_CREATE);
+        assert_eq!(plan.step_id("counter"), Some(Id::from(2)));
     }
 
     #[test]
-    fn convert_string_param() {
-        let value = String::from("hello world");
-        let expected_param_type = "string";
-        let expected_value = value.clone();
+    fn remove_step_keeps_references() {
+        let mut plan = Plan::new("alice");
+        let key = plan.add_step(Step::create_key(Key::Ed25519("alice".into())));
+        plan.add_labeled_step("bob", Step::create_key(Key::Ed25519("bob".into())));
+        let program = plan.add_labeled_step("counter", Step::create_program("counter.wasm"));
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "inc",
+            1000,
+            vec![program.into(), key.into()],
+        ));
 
-        let expected_json = json!({
-            "type": expected_param_type,
-            "value": &b64.encode(expected_value),
+        assert_eq!(
+            plan.remove_step(2),
+            Err(RemoveStepError::Referenced { step: 2, by: 3 })
+        );
+        assert_eq!(plan.remove_step(4), Err(RemoveStepError::NotFound(4)));
+        assert_eq!(plan.steps.len(), 4);
+
+        let removed = plan.remove_step(1).unwrap();
+
+        assert_eq!(removed, Step::create_key(Key::Ed25519("bob".into())));
+        assert_eq!(plan.steps[2].params, [Id::from(1).into(), key.into()]);
+        assert_eq!(plan.step_id("counter"), Some(Id::from(1)));
+        assert_eq!(plan.step_id("bob"), None);
+    }
+
+    #[test]
+    fn foreign_ids_are_not_remapped() {
+        let read = |id: Id| Step::new(Endpoint::ReadOnly, "get_value", 0, vec![id.into()]);
+
+        let mut other = Plan::new("alice");
+        other.create_keys([Key::Ed25519("alice".into()), Key::Ed25519("bob".into())]);
+        let foreign = other.add_step(Step::create_program("counter.wasm"));
+
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::create_key(Key::Ed25519("carol".into())));
+        plan.add_step(read(foreign));
+        plan.add_step(read(foreign));
+        plan.add_step(Step::create_key(Key::Ed25519("dave".into())));
+
+        // step 2 is in range, but the id points at a step of the other plan
+        assert_eq!(foreign, Id::from(2));
+
+        plan.dedup_consecutive();
+        assert_eq!(plan.steps.len(), 3);
+        assert_eq!(
+            plan.remove_step(2),
+            Ok(Step::create_key(Key::Ed25519("dave".into())))
+        );
+
+        plan.insert_step(0, Step::create_key(Key::Ed25519("erin".into())));
+        assert_eq!(plan.steps[2].params, [foreign.into()]);
+
+        plan.rebase_ids(&HashMap::from([(other.tag, 3)]));
+        assert_eq!(plan.steps[2].params, [Id::from(5).into()]);
+    }
+
+    #[test]
+    fn expected_errors() {
+        let responses = [
+            json!({ "id": 0, "error": "out of gas: 1000 units", "result": { "response": "", "timestamp": 0 } }),
+            json!({ "id": 1, "result": { "response": "", "timestamp": 0 } }),
+            json!({ "id": 2, "error": "invalid key", "result": { "response": "", "timestamp": 0 } }),
+        ];
+        let mut client = mock_client(responses.iter().map(|resp| Ok(plan_response(resp))));
+
+        let step = Step::new(Endpoint::Execute, "inc", 1000, vec![Id::from(0).into()])
+            .expect_error("out of gas");
+
+        let response = client._run_step("alice", &step).unwrap();
+        assert_eq!(
+            response.error_message().as_deref(),
+            Some("out of gas: 1000 units")
+        );
+
+        assert!(matches!(
+            client._run_step("alice", &step),
+            Err(StepError::UnexpectedSuccess { id: 1, expected }) if expected == "out of gas"
+        ));
+        assert!(matches!(
+            client._run_step("alice", &step),
+            Err(StepError::UnexpectedError { id: 2, actual, .. }) if actual == "invalid key"
+        ));
+
+        let input = String::from_utf8(client.writer).unwrap();
+        assert!(!input.contains("expect"));
+    }
+
+    #[test]
+    fn step_timeout_overrides_read_timeout() {
+        let responses = (0..2).map(|id| {
+            thread::sleep(Duration::from_millis(200));
+            Ok(plan_response(&json!({
+                "id": id,
+                "result": { "response": "", "timestamp": 0 },
+            })))
         });
+        let mut client = Client::new(Vec::new(), ThreadedResponses::spawn(responses))
+            .read_timeout(Duration::from_millis(10));
 
-        let param = Param::from(value.clone());
-        let expected_param = Param::String(value);
+        let step = Step::create_key(Key::Ed25519("alice".into()));
 
-        assert_eq!(param, expected_param);
+        let slow_step = step.clone().with_timeout(Duration::from_secs(5));
+        let response = client._run_step("alice", &slow_step).unwrap();
+        assert_eq!(response.base.id, 0);
 
-        let output_json = serde_json::to_value(&param).unwrap();
+        assert!(matches!(
+            client._run_step("alice", &step),
+            Err(StepError::Client(ClientError::Timeout(_)))
+        ));
+    }
 
-        assert_eq!(output_json, expected_json);
+    #[test]
+    fn collect_batch_responses() {
+        // responses may arrive in any order, and are matched back by id
+        let responses = [2, 0, 1].map(|id| {
+            Ok(plan_response(&json!({
+                "id": id,
+                "result": { "response": "", "timestamp": 0 },
+            })))
+        });
+        let mut client = mock_client(responses);
+
+        let mut plan = Plan::new("alice");
+        plan.create_keys(["alice", "bob", "carol"].map(|name| Key::Ed25519(name.into())));
+
+        let count = client.send_plan(&plan).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(
+            String::from_utf8(client.writer.clone())
+                .unwrap()
+                .lines()
+                .count(),
+            3
+        );
+
+        let responses = client.collect_responses(count).unwrap();
+        let ids: Vec<_> = responses.iter().map(|resp| resp.base.id).collect();
+        assert_eq!(ids, [0, 1, 2]);
+
+        assert!(matches!(
+            client.collect_responses(1),
+            Err(StepError::Client(ClientError::Eof))
+        ));
     }
 
     #[test]
-    fn convert_id_param() {
-        let value = 42;
-        let expected_param_type = "id";
-        let expected_value = format!("step_{value}");
+    fn run_step_tuple_response() {
+        let value = borsh::to_vec(&(42u64, String::from("alice"))).unwrap();
+        let response = json!({
+            "id": 0,
+            "result": { "response": b64.encode(value), "timestamp": 0 },
+        });
+        let mut client = mock_client(vec![Ok(plan_response(&response))]);
+
+        let step = Step::new(Endpoint::ReadOnly, "position", 0, vec![Id::from(0).into()]);
+        let response = client.run_step::<(u64, String)>("alice", &step).unwrap();
+
+        assert_eq!(response.result.response, Some((42, "alice".into())));
+    }
+
+    #[test]
+    fn run_step_carries_program_failure() {
+        let response = json!({
+            "id": 0,
+            "error": "program reverted",
+            "result": { "response": "", "timestamp": 0 },
+        });
+        let mut client = mock_client(vec![Ok(plan_response(&response))]);
+
+        let step = Step::new(
+            Endpoint::Execute,
+            "transfer",
+            1000,
+            vec![Id::from(0).into()],
+        );
+        let response = client.run_step::<u64>("alice", &step).unwrap();
+
+        assert_eq!(response.base.error.unwrap().to_string(), "program reverted");
+        assert_eq!(response.result.response, None);
+    }
+
+    #[test]
+    fn execute_surfaces_plan_error() {
+        let response = json!({
+            "id": 0,
+            "error": "out of gas",
+            "result": { "response": "", "timestamp": 0 },
+        });
+        let mut client = mock_client(vec![Ok(plan_response(&response))]);
+
+        let err = client
+            .execute::<()>("alice", "inc", 0, vec![Id::from(0).into()])
+            .unwrap_err();
+
+        assert!(matches!(err, StepError::Plan(err) if err.to_string() == "out of gas"));
+    }
 
+    #[test]
+    fn convert_time_params() {
+        let seconds = 1_700_000_000u64;
         let expected_json = json!({
-            "type": expected_param_type,
-            "value": &b64.encode(expected_value),
+            "type": "u64",
+            "value": &b64.encode(seconds.to_le_bytes()),
         });
 
-        let id = Id::from(value);
-        let param = Param::from(id);
-        let expected_param = Param::Id(id);
+        let timestamp = Param::from(UNIX_EPOCH + Duration::from_millis(seconds * 1000 + 999));
+        let duration = Param::from(Duration::from_secs(seconds));
 
-        assert_eq!(param, expected_param);
+        assert_eq!(serde_json::to_value(&timestamp).unwrap(), expected_json);
+        assert_eq!(serde_json::to_value(&duration).unwrap(), expected_json);
 
-        let output_json = serde_json::to_value(&param).unwrap();
+        let before_epoch = Param::Timestamp(UNIX_EPOCH - Duration::from_secs(1));
+        assert!(serde_json::to_value(&before_epoch).is_err());
+    }
 
-        assert_eq!(output_json, expected_json);
+    #[test]
+    fn cloned_plan_is_independent() {
+        let mut plan = Plan::new("alice");
+        let program = plan.add_step(Step::create_program("counter.wasm"));
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "inc",
+            1000,
+            vec![program.into(), 1u64.into()],
+        ));
+
+        let original = plan.clone();
+        let mut variation = plan.clone();
+        variation.steps[1].params[1] = Param::U64(2);
+        variation.add_step(Step::create_program("token.wasm"));
+
+        assert_eq!(plan, original);
+        assert_eq!(plan.steps[1].params[1], Param::U64(1));
+        assert_eq!(variation.steps.len(), 3);
     }
 
     #[test]
-    fn convert_key_param() {
-        let expected_param_type = "ed25519";
-        let expected_value = "id";
+    fn create_keys_returns_sequential_ids() {
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::create_program("counter.wasm"));
 
-        let expected_json = json!({
-            "type": expected_param_type,
-            "value": &b64.encode(expected_value),
+        let keys = ["alice", "bob", "carol"].map(|name| Key::Ed25519(name.into()));
+        let ids = plan.create_keys(keys.clone());
+
+        assert_eq!(ids, vec![Id::from(1), Id::from(2), Id::from(3)]);
+
+        for (id, key) in ids.iter().zip(keys) {
+            let index: &usize = id.into();
+            assert_eq!(plan.steps[*index], Step::create_key(key));
+        }
+    }
+
+    #[test]
+    fn param_display() {
+        let params = [
+            (Param::U64(42), "U64(42)"),
+            (Param::String("hello".into()), r#"String("hello")"#),
+            (Param::Id(Id::from(3)), "Id(step_3)"),
+            (
+                Param::Key(Key::Ed25519("alice".into())),
+                "Key(ed25519:alice)",
+            ),
+            (
+                Param::Key(Key::Secp256r1("bob".into())),
+                "Key(secp256r1:bob)",
+            ),
+            (
+                Param::Timestamp(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+                "Timestamp(1700000000)",
+            ),
+            (Param::Duration(Duration::from_secs(60)), "Duration(60s)"),
+        ];
+
+        for (param, expected) in params {
+            assert_eq!(param.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn response_return_data() {
+        let response = plan_response(&json!({
+            "id": 0,
+            "result": { "response": b64.encode(7u64.to_le_bytes()), "timestamp": 0 },
+        }));
+
+        assert_eq!(response.result.return_data(), 7u64.to_le_bytes());
+        assert_eq!(response.result.as_u64(), Some(7));
+    }
+
+    #[test]
+    fn response_hex() {
+        let response = plan_response(&json!({
+            "id": 0,
+            "result": { "response": "AAH/EA==", "timestamp": 0 },
+        }));
+
+        assert_eq!(response.result.response, [0x00, 0x01, 0xff, 0x10]);
+        assert_eq!(response.result.response_hex(), "0x0001ff10");
+        assert_eq!(response.result.response_base64(), "AAH/EA==");
+
+        let response = plan_response(&json!({
+            "id": 0,
+            "result": { "response": "", "timestamp": 0 },
+        }));
+
+        assert_eq!(response.result.response_hex(), "0x");
+    }
+
+    #[test]
+    fn responses_indexed_by_id() {
+        let responses = [3, 4, 5]
+            .map(|id| {
+                plan_response(&json!({
+                    "id": id,
+                    "result": { "id": format!("program_{id}"), "response": "", "timestamp": 0 },
+                }))
+            })
+            .into();
+
+        let by_id = responses_by_id(responses);
+
+        assert_eq!(by_id.len(), 3);
+        assert_eq!(by_id[&4].result.id.as_deref(), Some("program_4"));
+        assert!(!by_id.contains_key(&0));
+    }
+
+    #[test]
+    fn response_key_address() {
+        let response = plan_response(&json!({
+            "id": 0,
+            "result": {
+                "msg": "created named key with address matrix1qqxyz",
+                "response": "",
+                "timestamp": 0,
+            },
+        }));
+
+        assert_eq!(
+            response.result.message(),
+            Some("created named key with address matrix1qqxyz")
+        );
+        assert_eq!(response.result.key_address(), Some("matrix1qqxyz"));
+
+        let response = plan_response(&json!({
+            "id": 1,
+            "result": { "id": "2ut4fwdGE5FJG5w89CF3pVCjLrhiqCRZxB7ojtPnigh7QVU51i", "response": "", "timestamp": 0 },
+        }));
+
+        assert_eq!(response.result.message(), None);
+        assert_eq!(response.result.key_address(), None);
+    }
+
+    #[test]
+    fn response_success() {
+        let success = plan_response(&json!({
+            "id": 0,
+            "result": { "response": "", "timestamp": 0 },
+        }));
+
+        assert!(success.is_success());
+        assert_eq!(success.error_message(), None);
+
+        let failure = plan_response(&json!({
+            "id": 0,
+            "error": "out of gas",
+            "result": { "response": "", "timestamp": 0 },
+        }));
+
+        assert!(!failure.is_success());
+        assert_eq!(failure.error_message().as_deref(), Some("out of gas"));
+    }
+
+    #[test]
+    fn response_numbers_are_lossless() {
+        let line = format!(
+            r#"{{"id":{},"result":{{"balance":{max},"response":"","timestamp":{max}}}}}"#,
+            usize::MAX,
+            max = u64::MAX,
+        );
+        let response: PlanResponse = serde_json::from_str(&line).unwrap();
+
+        assert_eq!(response.base.id, usize::MAX);
+        assert_eq!(response.result.timestamp, u64::MAX);
+        assert_eq!(response.result.balance, u64::MAX);
+
+        // a float can't be stored without losing precision, so it is rejected
+        let line = r#"{"id":0,"result":{"response":"","timestamp":1.8446744073709552e19}}"#;
+        assert!(serde_json::from_str::<PlanResponse>(line).is_err());
+    }
+
+    #[test]
+    fn response_key_result() {
+        let response = plan_response(&json!({
+            "id": 1,
+            "result": {
+                "msg": "created named key with address matrix1qqxyz",
+                "response": "",
+                "timestamp": 1_697_835_142,
+            },
+        }));
+
+        assert_eq!(
+            response.key_result(),
+            KeyResult {
+                id: Id::from(1),
+                address: Some("matrix1qqxyz".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn response_typed_json() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Counter {
+            owner: String,
+            value: u64,
+        }
+
+        let response = |json: &str| {
+            plan_response(&json!({
+                "id": 0,
+                "result": { "response": b64.encode(json), "timestamp": 0 },
+            }))
+        };
+
+        let typed =
+            PlanResponseTypedJson::<Counter>::try_from(response(r#"{"owner":"alice","value":3}"#))
+                .unwrap();
+        assert_eq!(
+            typed.result.response,
+            Counter {
+                owner: "alice".into(),
+                value: 3,
+            }
+        );
+
+        assert!(PlanResponseTypedJson::<Counter>::try_from(response(r#"{"value":3}"#)).is_err());
+    }
+
+    #[test]
+    fn response_balance() {
+        let response = plan_response(&json!({
+            "id": 0,
+            "result": {
+                "id": "2ut4fwdGE5FJG5w89CF3pVCjLrhiqCRZxB7ojtPnigh7QVU51i",
+                "balance": 999_250,
+                "response": "",
+                "timestamp": 1_697_835_142,
+            },
+        }));
+
+        assert_eq!(response.result.balance, 999_250);
+
+        let typed = PlanResponseTyped::<()>::try_from(response).unwrap();
+        assert_eq!(typed.result.balance, 999_250);
+    }
+
+    #[test]
+    fn response_as_integers() {
+        let response = |bytes: &[u8]| {
+            plan_response(&json!({
+                "id": 0,
+                "result": { "response": b64.encode(bytes), "timestamp": 0 },
+            }))
+            .result
+        };
+
+        let result = response(&(-2i64).to_le_bytes());
+        assert_eq!(result.as_i64(), Some(-2));
+        assert_eq!(result.as_u64(), Some(u64::MAX - 1));
+        assert_eq!(result.as_u128(), None);
+
+        let result = response(&42u128.to_le_bytes());
+        assert_eq!(result.as_u128(), Some(42));
+        assert_eq!(result.as_u64(), None);
+    }
+
+    #[test]
+    fn plan_from_calls() {
+        let program = Id::from(0);
+        let calls = [
+            (
+                Endpoint::Execute,
+                "init".to_string(),
+                1000,
+                vec![program.into()],
+            ),
+            (
+                Endpoint::Execute,
+                "inc".to_string(),
+                2000,
+                vec![program.into(), 5u64.into()],
+            ),
+            (
+                Endpoint::ReadOnly,
+                "get_value".to_string(),
+                0,
+                vec![program.into()],
+            ),
+        ];
+
+        let plan = Plan::from_calls("alice", calls.clone());
+
+        assert_eq!(plan.caller_key, "alice");
+        assert_eq!(plan.steps.len(), 3);
+
+        for (step, (endpoint, method, max_units, params)) in plan.steps.into_iter().zip(calls) {
+            assert_eq!(step.endpoint, endpoint);
+            assert_eq!(step.method, method);
+            assert_eq!(step.max_units, max_units);
+            assert_eq!(step.params, params);
+        }
+    }
+
+    #[test]
+    fn convert_enum_param() {
+        #[derive(borsh::BorshSerialize)]
+        enum Action {
+            Transfer(u64, String),
+            Pause,
+        }
+
+        let unit = Param::Enum {
+            variant: 1,
+            fields: vec![],
+        };
+        let data = Param::Enum {
+            variant: 0,
+            fields: vec![Param::U64(42), Param::String("bob".into())],
+        };
+
+        let expected_unit = borsh::to_vec(&Action::Pause).unwrap();
+        let expected_data = borsh::to_vec(&Action::Transfer(42, "bob".into())).unwrap();
+
+        assert_eq!(expected_unit, [1]);
+        assert_eq!(
+            serde_json::to_value(&unit).unwrap(),
+            json!({ "type": "enum", "value": b64.encode(expected_unit) })
+        );
+        assert_eq!(
+            serde_json::to_value(&data).unwrap(),
+            json!({ "type": "enum", "value": b64.encode(expected_data) })
+        );
+
+        let nested_id = Param::Enum {
+            variant: 0,
+            fields: vec![Param::Id(Id::from(0))],
+        };
+        assert!(serde_json::to_value(&nested_id).is_err());
+    }
+
+    #[test]
+    fn convert_tuple_param() {
+        let address = Param::address_from_str(&to_hex(&[7; ADDRESS_LEN])).unwrap();
+        let tuple = Param::Tuple(vec![Param::U64(42), address]);
+
+        let mut expected = 42u64.to_le_bytes().to_vec();
+        expected.extend([7; ADDRESS_LEN]);
+
+        assert_eq!(
+            serde_json::to_value(&tuple).unwrap(),
+            json!({ "type": "tuple", "value": b64.encode(expected) })
+        );
+        assert!(tuple
+            .to_string()
+            .starts_with("Tuple([U64(42), Bytes(0x0707"));
+    }
+
+    #[test]
+    fn convert_program_id_param() {
+        let id = "2ut4fwdGE5FJG5w89CF3pVCjLrhiqCRZxB7ojtPnigh7QVU51i";
+        let param = Param::from_program_id(id).unwrap();
+
+        let Param::ProgramId(bytes) = param else {
+            panic!("expected a program id, got {param}");
+        };
+        assert_eq!(bytes[..4], [0xfb, 0xc4, 0x7b, 0xf5]);
+        assert_eq!(
+            serde_json::to_value(&param).unwrap(),
+            json!({ "type": "id", "value": b64.encode(bytes) })
+        );
+
+        assert_eq!(
+            Param::from_program_id("step_0"),
+            Err(ParamError::InvalidProgramId("step_0".into()))
+        );
+    }
+
+    #[test]
+    fn convert_hex_param() {
+        let expected = Param::Bytes(vec![0xde, 0xad, 0xbe, 0xef]);
+
+        assert_eq!(Param::from_hex("0xdeadbeef"), Ok(expected.clone()));
+        assert_eq!(Param::from_hex("DEADBEEF"), Ok(expected.clone()));
+        assert_eq!(expected.to_string(), "Bytes(0xdeadbeef)");
+        assert_eq!(
+            serde_json::to_value(&expected).unwrap(),
+            json!({ "type": "bytes", "value": b64.encode([0xde, 0xad, 0xbe, 0xef]) })
+        );
+
+        for invalid in ["0xabc", "0xzz", "0x+f"] {
+            assert_eq!(
+                Param::from_hex(invalid),
+                Err(ParamError::InvalidHex(invalid.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn convert_address_param() {
+        let expected: Vec<u8> = (0..=32).collect();
+
+        // the form printed for a created key, holding the public key
+        let bech32 = "matrix1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5z5tpwxqergd3c8g7rusqeca0t8";
+        assert_eq!(
+            Param::address_from_str(bech32),
+            Ok(Param::Bytes(expected.clone()))
+        );
+
+        let hex = to_hex(&expected);
+        assert_eq!(Param::address_from_str(&hex), Ok(Param::Bytes(expected)));
+
+        for invalid in ["matrix1qqqq", "0xdeadbeef"] {
+            assert_eq!(
+                Param::address_from_str(invalid),
+                Err(ParamError::InvalidAddress(invalid.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn convert_public_key_param() {
+        let public_key: Vec<u8> = (1..=32).collect();
+        let bech32 = "matrix1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5z5tpwxqergd3c8g7rusqeca0t8";
+
+        let address = Param::address_from_public_key(&public_key).unwrap();
+        assert_eq!(Ok(address.clone()), Param::address_from_str(bech32));
+        assert_eq!(address, Param::Bytes((0..=32).collect()));
+
+        assert_eq!(
+            Param::address_from_public_key(&public_key[1..]),
+            Err(ParamError::InvalidPublicKeyLength {
+                expected: 32,
+                actual: 31,
+            })
+        );
+    }
+
+    #[test]
+    fn find_crate_artifact() {
+        let root =
+            std::env::temp_dir().join(format!("simulator-find-artifact-{}", std::process::id()));
+        let release = root.join("target/wasm32-unknown-unknown/release");
+        std::fs::create_dir_all(&release).unwrap();
+        std::fs::write(release.join("my_token.wasm"), b"\0asm").unwrap();
+
+        let dirs = || [root.join("build"), root.join("target")];
+
+        let expected = release.join("my_token.wasm").canonicalize().unwrap();
+        let found = find_artifact(dirs(), "my-token", Profile::Release);
+        let missing = find_artifact(dirs(), "my-token", Profile::Debug);
+
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(found.unwrap(), expected);
+        assert!(matches!(missing, Err(StepError::ProgramNotFound(tried)) if tried.len() == 2));
+    }
+
+    #[test]
+    fn resolve_caller_address() {
+        let step = Step::new(
+            Endpoint::Execute,
+            "mint",
+            1000,
+            vec![Id::from(0).into(), Param::CallerAddress],
+        );
+
+        let serialized = serde_json::to_value(SimulatorStep {
+            caller_key: "alice",
+            step: &step,
+            encoding: ParamEncoding::default(),
+        })
+        .unwrap();
+
+        assert_eq!(
+            serialized["params"][1],
+            json!({ "type": "ed25519", "value": b64.encode("alice") })
+        );
+        assert!(serde_json::to_value(Param::CallerAddress).is_err());
+    }
+
+    #[test]
+    fn run_step_json_response() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Position {
+            owner: String,
+            amount: u64,
+        }
+
+        let json = br#"{"owner":"alice","amount":42}"#;
+        let responses = [json, b"[]".as_slice()].map(|response| {
+            Ok(plan_response(&json!({
+                "id": 0,
+                "result": { "response": b64.encode(response), "timestamp": 0 },
+            })))
         });
+        let mut client = mock_client(responses);
 
-        let key = Key::Ed25519(expected_value.to_string());
-        let param = Param::from(key.clone());
-        let expected_param = Param::Key(key);
+        let step = Step::new(Endpoint::ReadOnly, "position", 0, vec![Id::from(0).into()]);
 
-        assert_eq!(param, expected_param);
+        let response = client.run_step_json::<Position>("alice", &step).unwrap();
+        assert_eq!(
+            response.result.response,
+            Some(Position {
+                owner: "alice".into(),
+                amount: 42,
+            })
+        );
 
-        let output_json = serde_json::to_value(&param).unwrap();
+        assert!(matches!(
+            client.run_step_json::<Position>("alice", &step),
+            Err(StepError::Serde(_))
+        ));
+    }
 
-        assert_eq!(output_json, expected_json);
+    #[test]
+    fn checked_step_ids() {
+        let mut plan = Plan::new("alice");
+        let program = plan.add_step(Step::create_program("counter.wasm"));
+
+        assert_eq!(plan.id_for(0).unwrap(), program);
+        assert_eq!(
+            plan.id_for(1).unwrap_err().to_string(),
+            "step 1 is out of range for a plan of 1 steps"
+        );
+    }
+
+    #[test]
+    fn bind_placeholders() {
+        let mut plan = Plan::new("alice");
+        let program = plan.add_step(Step::create_program("token.wasm"));
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "transfer",
+            1000,
+            vec![
+                program.into(),
+                Param::Placeholder("recipient".into()),
+                Param::Enum {
+                    variant: 0,
+                    fields: vec![Param::Placeholder("amount".into())],
+                },
+            ],
+        ));
+
+        let response = json!({ "id": 0, "result": { "response": "", "timestamp": 0 } });
+        let mut client = mock_client([Ok(plan_response(&response))]);
+        assert!(matches!(
+            client.run_plan(plan.clone()),
+            Err(StepError::Serde(err)) if err.to_string().contains("unbound placeholder")
+        ));
+        // only the deploy was sent
+        assert_eq!(String::from_utf8(client.writer).unwrap().lines().count(), 1);
+
+        plan.bind("recipient", Key::Ed25519("bob".into()).into());
+        plan.bind("amount", 100u64.into());
+
+        let params = serde_json::to_value(&plan.steps[1].params).unwrap();
+        assert_eq!(
+            params,
+            json!([
+                { "type": "id", "value": b64.encode("step_0") },
+                { "type": "ed25519", "value": b64.encode("bob") },
+                { "type": "enum", "value": b64.encode([0, 100, 0, 0, 0, 0, 0, 0, 0]) },
+            ])
+        );
+    }
+
+    #[test]
+    fn convert_bytes_with_len_param() {
+        let bytes = |len_prefix| Param::BytesWithLen {
+            bytes: vec![7; 3],
+            len_prefix,
+        };
+
+        assert_eq!(
+            serde_json::to_value(bytes(LenPrefix::U32)).unwrap(),
+            json!({ "type": "bytes", "value": b64.encode([3, 0, 0, 0, 7, 7, 7]) })
+        );
+        assert_eq!(
+            serde_json::to_value(bytes(LenPrefix::U64)).unwrap(),
+            json!({ "type": "bytes", "value": b64.encode([3, 0, 0, 0, 0, 0, 0, 0, 7, 7, 7]) })
+        );
+        assert_eq!(
+            bytes(LenPrefix::U64).to_string(),
+            "BytesWithLen(u64, 0x070707)"
+        );
+    }
+
+    #[test]
+    fn convert_string_vec_param() {
+        let tags = Param::from(vec!["a".to_string(), "bb".to_string()]);
+
+        let expected = [2, 0, 0, 0, 1, 0, 0, 0, b'a', 2, 0, 0, 0, b'b', b'b'];
+        assert_eq!(
+            serde_json::to_value(&tags).unwrap(),
+            json!({ "type": "bytes", "value": b64.encode(expected) })
+        );
+        assert_eq!(tags.to_string(), r#"StringVec(["a", "bb"])"#);
+    }
+
+    #[test]
+    fn convert_signature_param() {
+        let signature = Param::signature(SignatureKind::Ed25519, vec![7; 64]).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&signature).unwrap(),
+            json!({ "type": "bytes", "value": b64.encode([7; 64]) })
+        );
+        assert!(signature
+            .to_string()
+            .starts_with("Signature(ed25519, 0x0707"));
+
+        assert_eq!(
+            Param::signature(SignatureKind::Secp256r1, vec![7; 65]),
+            Err(ParamError::InvalidSignatureLength {
+                algorithm: SignatureKind::Secp256r1,
+                expected: 64,
+                actual: 65,
+            })
+        );
+
+        let unchecked = Param::Signature {
+            algorithm: SignatureKind::Ed25519,
+            bytes: vec![7; 32],
+        };
+        assert!(serde_json::to_value(&unchecked).is_err());
     }
 }
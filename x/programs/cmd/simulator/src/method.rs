@@ -0,0 +1,69 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+#[error("invalid method name: {0:?}")]
+pub struct InvalidMethod(pub String);
+
+/// The name of a program function, checked to be a valid identifier so typos
+/// like `"trans fer"` are caught before reaching the simulator.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Method(String);
+
+impl Method {
+    /// Creates a [Method] from `name`, ignoring surrounding whitespace.
+    /// # Errors
+    ///
+    /// Returns an error if `name` is not a valid identifier.
+    pub fn new<S: AsRef<str>>(name: S) -> Result<Self, InvalidMethod> {
+        let name = name.as_ref().trim();
+
+        if is_valid(name) {
+            Ok(Method(name.into()))
+        } else {
+            Err(InvalidMethod(name.into()))
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Method names are identifiers: an ascii letter or `_`, followed by ascii
+/// alphanumerics or `_`.
+pub(crate) fn is_valid(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<Method> for String {
+    fn from(method: Method) -> Self {
+        method.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn method_validation() {
+        assert_eq!(Method::new(" transfer\n").unwrap().as_str(), "transfer");
+        assert_eq!(Method::new("_get_value2").unwrap().as_str(), "_get_value2");
+
+        for name in ["", "trans fer", "2transfer", "transfer!"] {
+            assert_eq!(Method::new(name), Err(InvalidMethod(name.into())));
+        }
+    }
+}
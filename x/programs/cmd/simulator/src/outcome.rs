@@ -0,0 +1,170 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    time::Duration,
+};
+
+use crate::{Endpoint, Id, Plan, PlanResponse, PROGRAM_CREATE};
+
+/// The responses of a [Plan] run, which remembers the steps that created
+/// programs so their program ids can be looked up by step [Id], and the
+/// labels of the [Plan] to look up responses by, and the `max_units` of its
+/// calls to work out the units they consumed. Steps are looked up by the
+/// [Id]s the [Plan] returned, also when it did not run first in its session.
+#[derive(Debug)]
+pub struct RunOutcome {
+    responses: Vec<PlanResponse>,
+    deploys: HashSet<usize>,
+    labels: HashMap<String, Id>,
+    max_units: HashMap<usize, u64>,
+}
+
+impl RunOutcome {
+    pub(crate) fn new(plan: &Plan, responses: Vec<PlanResponse>) -> Self {
+        let deploys = plan
+            .steps
+            .iter()
+            .enumerate()
+            .filter(|(_, step)| step.endpoint == Endpoint::Execute && step.method == PROGRAM_CREATE)
+            .map(|(i, _)| i)
+            .collect();
+
+        let max_units = plan
+            .steps
+            .iter()
+            .enumerate()
+            .filter(|(_, step)| step.endpoint == Endpoint::Execute && step.method != PROGRAM_CREATE)
+            .map(|(i, step)| (i, step.max_units))
+            .collect();
+
+        Self {
+            responses,
+            deploys,
+            labels: plan.labels.clone(),
+            max_units,
+        }
+    }
+
+    /// Returns the id of the program created by the `step`, or `None` if the
+    /// step did not create a program.
+    #[must_use]
+    pub fn program_id(&self, step: Id) -> Option<&str> {
+        let step: &usize = (&step).into();
+
+        if !self.deploys.contains(step) {
+            return None;
+        }
+
+        self.response(*step)?.result.id.as_deref()
+    }
+
+    /// Returns the response of the `step`, or `None` if it was not run.
+    #[must_use]
+    pub fn get(&self, step: Id) -> Option<&PlanResponse> {
+        self.response(*<&usize>::from(&step))
+    }
+
+    /// Iterates over the responses of the run along with the [Id] of their
+    /// step in the [Plan].
+    pub fn iter(&self) -> impl Iterator<Item = (Id, &PlanResponse)> {
+        self.responses
+            .iter()
+            .enumerate()
+            .map(|(step, resp)| (Id::from(step), resp))
+    }
+
+    /// Returns the response of the step added with the `label`, see
+    /// [`Plan::add_labeled_step`].
+    #[must_use]
+    pub fn by_label(&self, label: &str) -> Option<&PlanResponse> {
+        let step = self.labels.get(label)?;
+        self.response(*<&usize>::from(step))
+    }
+
+    /// Returns the units consumed by the [`Endpoint::Execute`] call of the
+    /// `step`, its `max_units` less the [`balance`](crate::PlanResult::balance)
+    /// left, or `None` if the step is not a successful call.
+    #[must_use]
+    pub fn units_consumed(&self, step: Id) -> Option<u64> {
+        let step = *<&usize>::from(&step);
+        let max_units = self.max_units.get(&step)?;
+        let response = self.response(step).filter(|resp| resp.is_success())?;
+
+        Some(max_units.saturating_sub(response.result.balance))
+    }
+
+    /// Returns the units consumed by all the [`Endpoint::Execute`] calls of
+    /// the run, see [`RunOutcome::units_consumed`].
+    #[must_use]
+    pub fn total_units(&self) -> u64 {
+        self.max_units
+            .keys()
+            .filter_map(|&step| self.units_consumed(Id::from(step)))
+            .sum()
+    }
+
+    /// Looks up the response by the position of its step in the [Plan], as
+    /// the ids the simulator reports count from the start of its session.
+    fn response(&self, step: usize) -> Option<&PlanResponse> {
+        self.responses.get(step)
+    }
+
+    #[must_use]
+    pub fn responses(&self) -> &[PlanResponse] {
+        &self.responses
+    }
+
+    #[must_use]
+    pub fn into_responses(self) -> Vec<PlanResponse> {
+        self.responses
+    }
+}
+
+/// A summary of a [Plan] run, see [`Client::run_plan_report`](crate::Client::run_plan_report).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunReport {
+    /// The number of steps run.
+    pub steps: usize,
+    /// The number of steps the simulator reported no error for.
+    pub succeeded: usize,
+    /// The number of steps the simulator reported an error for, including
+    /// those expected to fail with [`Step::expect_error`](crate::Step::expect_error).
+    pub failed: usize,
+    /// The units consumed by the run, see [`RunOutcome::total_units`].
+    pub total_units: u64,
+    /// The wall time of the run.
+    pub elapsed: Duration,
+}
+
+impl RunReport {
+    pub(crate) fn new(outcome: &RunOutcome, elapsed: Duration) -> Self {
+        let succeeded = outcome
+            .responses
+            .iter()
+            .filter(|resp| resp.is_success())
+            .count();
+
+        Self {
+            steps: outcome.responses.len(),
+            succeeded,
+            failed: outcome.responses.len() - succeeded,
+            total_units: outcome.total_units(),
+            elapsed,
+        }
+    }
+}
+
+/// Renders the report on one line, e.g. for CI logs.
+impl fmt::Display for RunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} steps: {} succeeded, {} failed, {} units in {:.3}s",
+            self.steps,
+            self.succeeded,
+            self.failed,
+            self.total_units,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
@@ -0,0 +1,103 @@
+use std::{io::Write, sync::Mutex, thread};
+
+use crate::{Client, ClientBuilder, ClientError, Plan, PlanResponse, StepError, StepResult};
+
+type PlanOutput = Result<Vec<PlanResponse>, StepError>;
+
+impl ClientBuilder {
+    /// Runs the independent `plans` on at most `workers` simulators at a time,
+    /// returning their results in the order of `plans`.
+    ///
+    /// Each plan runs in a freshly spawned simulator, which exits once the
+    /// plan is done: [Id](crate::Id)s are numbered from the start of a
+    /// simulator session, so plans cannot share one.
+    #[must_use]
+    pub fn run_plans_parallel(&self, plans: Vec<Plan>, workers: usize) -> Vec<PlanOutput> {
+        run_parallel(plans, workers, || self.try_build())
+    }
+}
+
+fn run_parallel<F, W, R>(plans: Vec<Plan>, workers: usize, connect: F) -> Vec<PlanOutput>
+where
+    F: Fn() -> Result<Client<W, R>, ClientError> + Sync,
+    W: Write,
+    R: Iterator<Item = StepResult>,
+{
+    let count = plans.len();
+    let queue = Mutex::new(plans.into_iter().enumerate());
+
+    let run_next = || -> Vec<(usize, PlanOutput)> {
+        let mut outputs = Vec::new();
+
+        loop {
+            // the guard is dropped before the plan runs
+            let next = queue.lock().unwrap_or_else(|err| err.into_inner()).next();
+
+            let Some((index, plan)) = next else {
+                return outputs;
+            };
+
+            // the client, and its simulator, are dropped once the plan is done
+            let output = connect()
+                .map_err(StepError::Client)
+                .and_then(|mut client| client.run_plan(plan));
+
+            outputs.push((index, output));
+        }
+    };
+
+    let mut outputs: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.clamp(1, count.max(1)))
+            .map(|_| scope.spawn(run_next))
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("plan worker panicked"))
+            .collect()
+    });
+
+    outputs.sort_by_key(|(index, _)| *index);
+    outputs.into_iter().map(|(_, output)| output).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Key;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn runs_every_plan_in_order() {
+        // plan `i` has `i + 1` steps, so the outputs show which plan they belong to
+        let plans = (0..10)
+            .map(|i| {
+                let mut plan = Plan::new("alice");
+                plan.create_keys((0..=i).map(|j| Key::Ed25519(format!("key_{j}"))));
+                plan
+            })
+            .collect();
+
+        let connected = AtomicUsize::new(0);
+        let outputs = run_parallel(plans, 3, || {
+            connected.fetch_add(1, Ordering::Relaxed);
+            let response = json!({ "id": 0, "result": { "response": "", "timestamp": 0 } });
+
+            Ok(Client::new(
+                Vec::new(),
+                std::iter::repeat_with(move || {
+                    Ok(serde_json::from_str(&response.to_string()).unwrap())
+                }),
+            ))
+        });
+
+        assert_eq!(connected.load(Ordering::Relaxed), 10);
+
+        let lens: Vec<_> = outputs
+            .into_iter()
+            .map(|output| output.unwrap().len())
+            .collect();
+        assert_eq!(lens, (1..=10).collect::<Vec<_>>());
+    }
+}
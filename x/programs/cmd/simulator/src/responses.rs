@@ -0,0 +1,331 @@
+use std::{
+    io::{self, BufRead, Read},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Mutex, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+
+#[cfg(feature = "client")]
+use std::io::Write;
+
+use crate::{BaseResponse, ClientError, PlanResponse, PlanResult, StepError, StepResult};
+
+/// An iterator over the [`PlanResponse`](crate::PlanResponse)s the simulator writes, one per line.
+pub struct Responses<B> {
+    reader: B,
+    skip_non_json: bool,
+    max_response_bytes: Option<usize>,
+}
+
+impl<B: BufRead> Responses<B> {
+    #[must_use]
+    pub fn new(reader: B) -> Self {
+        Self {
+            reader,
+            skip_non_json: true,
+            max_response_bytes: None,
+        }
+    }
+
+    /// Fails with [`ClientError::ResponseTooLarge`] on lines longer than
+    /// `max` bytes, instead of buffering them whole. The rest of such a line
+    /// is skipped. Lines are not limited by default.
+    #[must_use]
+    pub fn max_response_bytes(mut self, max: usize) -> Self {
+        self.max_response_bytes = Some(max);
+        self
+    }
+
+    /// Whether to skip lines that are not JSON objects, such as log output
+    /// leaking to stdout or blank lines, instead of failing on them. Enabled
+    /// by default. When disabled, a blank line fails with
+    /// [`ClientError::EmptyResponse`].
+    #[must_use]
+    pub fn skip_non_json(mut self, skip: bool) -> Self {
+        self.skip_non_json = skip;
+        self
+    }
+}
+
+impl<B: BufRead> Iterator for Responses<B> {
+    type Item = StepResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.read_line()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(StepError::Client(err))),
+            };
+
+            if !self.skip_non_json && line.trim().is_empty() {
+                return Some(Err(StepError::Client(ClientError::EmptyResponse)));
+            }
+
+            let response = serde_json::from_str(&line);
+
+            if self.skip_non_json && response.is_err() && !is_json_object(&line) {
+                continue;
+            }
+
+            return Some(response.map_err(StepError::Serde));
+        }
+    }
+}
+
+impl<B: BufRead> Responses<B> {
+    /// Reads the next line without its line ending, or `None` at the end of
+    /// the output.
+    fn read_line(&mut self) -> Option<Result<String, ClientError>> {
+        // room for a "\r\n" line ending, to tell a line that is too long
+        let limit = self
+            .max_response_bytes
+            .map_or(u64::MAX, |max| max as u64 + 2);
+        let mut line = Vec::new();
+
+        match (&mut self.reader).take(limit).read_until(b'\n', &mut line) {
+            Ok(0) => return None,
+            Ok(_) => {}
+            Err(err) => return Some(Err(ClientError::Read(err))),
+        }
+
+        let terminated = line.last() == Some(&b'\n');
+
+        if terminated {
+            line.pop();
+
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+        }
+
+        if let Some(max) = self.max_response_bytes.filter(|&max| line.len() > max) {
+            let skipped = if terminated {
+                Ok(())
+            } else {
+                skip_line(&mut self.reader)
+            };
+
+            return Some(skipped.and(Err(ClientError::ResponseTooLarge(max))));
+        }
+
+        Some(
+            String::from_utf8(line)
+                .map_err(|err| ClientError::Read(io::Error::new(io::ErrorKind::InvalidData, err))),
+        )
+    }
+}
+
+/// Consumes the rest of the current line of `reader`.
+fn skip_line(reader: &mut impl BufRead) -> Result<(), ClientError> {
+    loop {
+        let buf = reader.fill_buf()?;
+
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        match buf.iter().position(|&b| b == b'\n') {
+            Some(end) => {
+                reader.consume(end + 1);
+                return Ok(());
+            }
+            None => {
+                let len = buf.len();
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+/// An iterator over responses read on a background thread, so that waiting
+/// for a response can [time out](Self::next_timeout) instead of blocking on
+/// the simulator's output.
+pub struct ThreadedResponses {
+    // only accessed through `&mut self`, the mutex just makes the iterator `Sync`
+    receiver: Mutex<Receiver<StepResult>>,
+}
+
+impl ThreadedResponses {
+    /// Reads `responses` on a new thread, which exits once they run out or
+    /// this iterator is dropped.
+    #[must_use]
+    pub fn spawn<I>(responses: I) -> Self
+    where
+        I: Iterator<Item = StepResult> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for response in responses {
+                if sender.send(response).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    /// Waits at most `timeout` for the next response, failing with
+    /// [`ClientError::Timeout`] when none arrives in time. A response arriving
+    /// after its timeout is returned by the next call.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<StepResult> {
+        let receiver = self
+            .receiver
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        match receiver.recv_timeout(timeout) {
+            Ok(response) => Some(response),
+            Err(RecvTimeoutError::Timeout) => {
+                Some(Err(StepError::Client(ClientError::Timeout(timeout))))
+            }
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+impl Iterator for ThreadedResponses {
+    type Item = StepResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .recv()
+            .ok()
+    }
+}
+
+/// An endless iterator of successful, empty [`PlanResponse`]s with sequential
+/// ids, standing in for the simulator in a [`Client::dry_run`](crate::Client::dry_run).
+#[derive(Debug, Default)]
+pub struct DryRunResponses {
+    next_id: usize,
+}
+
+impl Iterator for DryRunResponses {
+    type Item = StepResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        Some(Ok(PlanResponse {
+            base: BaseResponse { id, error: None },
+            result: PlanResult {
+                id: None,
+                msg: None,
+                timestamp: 0,
+                response: Vec::new(),
+                balance: 0,
+            },
+        }))
+    }
+}
+
+/// A reader that copies everything read from `reader` to `copy`.
+#[cfg(feature = "client")]
+pub(crate) struct Tee<R, W> {
+    reader: R,
+    copy: W,
+}
+
+#[cfg(feature = "client")]
+impl<R, W> Tee<R, W> {
+    pub(crate) fn new(reader: R, copy: W) -> Self {
+        Self { reader, copy }
+    }
+}
+
+#[cfg(feature = "client")]
+impl<R: Read, W: Write> Read for Tee<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.copy.write_all(&buf[..read])?;
+        self.copy.flush()?;
+
+        Ok(read)
+    }
+}
+
+fn is_json_object(line: &str) -> bool {
+    serde_json::from_str::<serde_json::Map<_, _>>(line).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    const LOG_LINE: &str = "program output: hello";
+    const RESPONSE: &str = r#"{"id":3,"result":{"response":"","timestamp":0}}"#;
+
+    #[test]
+    fn skips_non_json_lines() {
+        let input = format!("{LOG_LINE}\n{RESPONSE}\n");
+        let mut responses = Responses::new(Cursor::new(input));
+
+        let response = responses.next().unwrap().unwrap();
+
+        assert_eq!(response.base.id, 3);
+        assert!(responses.next().is_none());
+    }
+
+    #[test]
+    fn fails_on_non_json_lines_when_not_skipping() {
+        let input = format!("{LOG_LINE}\n{RESPONSE}\n");
+        let mut responses = Responses::new(Cursor::new(input)).skip_non_json(false);
+
+        assert!(matches!(responses.next(), Some(Err(StepError::Serde(_)))));
+    }
+
+    #[test]
+    fn blank_lines() {
+        let input = format!(" \n{RESPONSE}\n");
+
+        let mut responses = Responses::new(Cursor::new(input.clone()));
+        assert_eq!(responses.next().unwrap().unwrap().base.id, 3);
+
+        let mut responses = Responses::new(Cursor::new(input)).skip_non_json(false);
+        assert!(matches!(
+            responses.next(),
+            Some(Err(StepError::Client(ClientError::EmptyResponse)))
+        ));
+    }
+
+    #[test]
+    fn fails_on_oversized_lines() {
+        let flood = "x".repeat(1000);
+        let input = format!("{flood}\n{RESPONSE}\r\n");
+
+        let mut responses = Responses::new(BufReader::with_capacity(16, Cursor::new(input)))
+            .max_response_bytes(RESPONSE.len());
+
+        assert!(matches!(
+            responses.next(),
+            Some(Err(StepError::Client(ClientError::ResponseTooLarge(max)))) if max == RESPONSE.len()
+        ));
+        // the rest of the oversized line is skipped
+        assert_eq!(responses.next().unwrap().unwrap().base.id, 3);
+        assert!(responses.next().is_none());
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn tee_copies_raw_output() {
+        let input = format!("{LOG_LINE}\n{RESPONSE}\n");
+        let mut transcript = Vec::new();
+
+        let tee = Tee::new(Cursor::new(input.clone()), &mut transcript);
+        let responses: Vec<_> = Responses::new(BufReader::new(tee)).collect();
+
+        assert_eq!(responses.len(), 1);
+        assert_eq!(String::from_utf8(transcript).unwrap(), input);
+    }
+}
@@ -0,0 +1,499 @@
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+use crate::{method, Endpoint, Id, Param, Plan, PROGRAM_CREATE};
+
+const WASM_MAGIC: &[u8; 4] = b"\0asm";
+
+#[derive(Error, Debug)]
+pub enum ValidationError {
+    #[error("step {step}: cannot read program {path}: {source}")]
+    ProgramNotFound {
+        step: usize,
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("step {step}: {path} is not a wasm program")]
+    InvalidWasm { step: usize, path: PathBuf },
+    #[error("step {step}: invalid method name {method:?}")]
+    InvalidMethod { step: usize, method: String },
+    #[error("step {step}: called {method:?} with {actual} params but it takes {expected}")]
+    ParamCount {
+        step: usize,
+        method: String,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("step {step}: depends on step {dependency}, which is not in the plan")]
+    UnknownDependency { step: usize, dependency: usize },
+    #[error("step {step}: depends on itself through a cycle of dependencies")]
+    DependencyCycle { step: usize },
+    /// The simulator has no pre-existing keys, so the caller key must be
+    /// created by a step before the first call, or be
+    /// [registered](Plan::register_existing_key) as created beforehand, e.g.
+    /// by an earlier plan of the same session.
+    #[error("step {step}: caller key {caller_key:?} is not created before the step")]
+    UnknownCaller { step: usize, caller_key: String },
+    #[error(transparent)]
+    Warning(#[from] ValidationWarning),
+}
+
+/// A likely mistake in a [Plan] that does not stop it from running.
+#[derive(Error, Debug, PartialEq)]
+pub enum ValidationWarning {
+    #[error("step {step}: execute step has zero max units and will run out of gas")]
+    ZeroMaxUnits { step: usize },
+    #[error("step {step}: read-only step has {max_units} max units, which are not used")]
+    ReadOnlyMaxUnits { step: usize, max_units: u64 },
+    /// The id was returned by another [Plan]. It only points at the intended
+    /// step if both plans run with
+    /// [`Client::run_plans_sequential`](crate::Client::run_plans_sequential),
+    /// which rebases it.
+    #[error("step {step}: {id} was returned by another plan")]
+    ForeignId { step: usize, id: Id },
+}
+
+impl Plan<'_> {
+    /// Registers that `method` takes `param_count` params, not counting the
+    /// program, so that [`Plan::validate`] rejects calls passing a different
+    /// number of params.
+    pub fn register_signature<S: Into<String>>(&mut self, method: S, param_count: usize) {
+        self.signatures.insert(method.into(), param_count);
+    }
+
+    /// Registers that the key `identifier` exists before the [Plan] runs, e.g.
+    /// as it was created by an earlier plan of the same session, so that
+    /// [`Plan::validate`] accepts it as the caller without a
+    /// [`Step::create_key`](crate::Step::create_key) step.
+    pub fn register_existing_key<S: Into<String>>(&mut self, identifier: S) {
+        self.existing_keys.insert(identifier.into());
+    }
+
+    /// Checks the [Plan] for mistakes that would otherwise only surface once
+    /// it runs in the simulator. Every method name must be a valid identifier
+    /// (see [`Method`](crate::Method)), and every program created by the plan
+    /// must exist and start with the wasm magic bytes. The dependencies of the
+    /// steps must exist and not form a cycle, see [`Plan::execution_order`].
+    /// Calls to a method with
+    /// a [registered signature](Plan::register_signature) must pass the
+    /// registered number of params. The caller key must be created by a step
+    /// before the first call, or be
+    /// [registered as existing](Plan::register_existing_key).
+    ///
+    /// Likely mistakes, such as an [`Endpoint::Execute`] call with zero
+    /// `max_units`, an [`Endpoint::ReadOnly`] call with nonzero `max_units`
+    /// or [`Param::Id`]s returned by another plan, are returned as warnings.
+    ///
+    /// Relative program paths are resolved against the current directory. Use
+    /// [`Plan::validate_in`] for a client started in another directory with
+    /// [`ClientBuilder::current_dir`](crate::ClientBuilder::current_dir).
+    /// # Errors
+    ///
+    /// Returns the first problem found.
+    pub fn validate(&self) -> Result<Vec<ValidationWarning>, ValidationError> {
+        self.validate_in("")
+    }
+
+    /// Like [`Plan::validate`], but resolves relative program paths against
+    /// `dir`, the directory the simulator runs in.
+    /// # Errors
+    ///
+    /// Returns the first problem found.
+    pub fn validate_in<P: AsRef<Path>>(
+        &self,
+        dir: P,
+    ) -> Result<Vec<ValidationWarning>, ValidationError> {
+        let mut warnings = Vec::new();
+        let mut caller_created = self.existing_keys.contains(self.caller_key);
+
+        for (index, step) in self.steps.iter().enumerate() {
+            if !method::is_valid(&step.method) {
+                return Err(ValidationError::InvalidMethod {
+                    step: index,
+                    method: step.method.clone(),
+                });
+            }
+
+            for &id in step.referenced_ids() {
+                if id.plan().is_some_and(|tag| tag != self.tag) {
+                    warnings.push(ValidationWarning::ForeignId { step: index, id });
+                }
+            }
+
+            if step.endpoint == Endpoint::Key {
+                caller_created |= self
+                    .created_key(Id::from(index))
+                    .is_some_and(|key| key.identifier() == self.caller_key);
+
+                continue;
+            }
+
+            if !caller_created {
+                return Err(ValidationError::UnknownCaller {
+                    step: index,
+                    caller_key: self.caller_key.into(),
+                });
+            }
+
+            if let Some(&expected) = self.signatures.get(&step.method) {
+                // the first param is the program called
+                let actual = step.params.len().saturating_sub(1);
+
+                if actual != expected {
+                    return Err(ValidationError::ParamCount {
+                        step: index,
+                        method: step.method.clone(),
+                        expected,
+                        actual,
+                    });
+                }
+            }
+
+            if step.endpoint == Endpoint::ReadOnly && step.max_units != 0 {
+                warnings.push(ValidationWarning::ReadOnlyMaxUnits {
+                    step: index,
+                    max_units: step.max_units,
+                });
+            }
+
+            if step.endpoint != Endpoint::Execute {
+                continue;
+            }
+
+            if step.method != PROGRAM_CREATE {
+                if step.max_units == 0 {
+                    warnings.push(ValidationWarning::ZeroMaxUnits { step: index });
+                }
+
+                continue;
+            }
+
+            if let Some(Param::String(path)) = step.params.first() {
+                validate_program(index, dir.as_ref().join(path))?;
+            }
+        }
+
+        self.execution_order()?;
+
+        Ok(warnings)
+    }
+
+    /// Returns the steps in an order where each step comes after the steps it
+    /// references with [`Param::Id`]s or [depends on](crate::Step::depends_on),
+    /// keeping the order of the plan where possible. Only ids referencing
+    /// earlier steps are followed, as others may reference steps of an earlier
+    /// plan of the same session, but dependencies must be steps of the plan.
+    /// # Errors
+    ///
+    /// Returns an error if a dependency is not a step of the plan, or if
+    /// dependencies form a cycle.
+    pub fn execution_order(&self) -> Result<Vec<Id>, ValidationError> {
+        let count = self.steps.len();
+        let mut dependents = vec![Vec::new(); count];
+        let mut pending = vec![0; count];
+
+        for (index, step) in self.steps.iter().enumerate() {
+            for &dependency in &step.dependencies {
+                let dependency = *<&usize>::from(&dependency);
+
+                if dependency >= count {
+                    return Err(ValidationError::UnknownDependency {
+                        step: index,
+                        dependency,
+                    });
+                }
+            }
+
+            let references = step.params.iter().filter_map(|param| match param {
+                Param::Id(id) => Some(*<&usize>::from(id)),
+                _ => None,
+            });
+            let mut dependencies: Vec<usize> = references
+                .filter(|&reference| reference < index)
+                .chain(step.dependencies.iter().map(|id| *<&usize>::from(id)))
+                .collect();
+            dependencies.sort_unstable();
+            dependencies.dedup();
+
+            for dependency in dependencies {
+                dependents[dependency].push(index);
+                pending[index] += 1;
+            }
+        }
+
+        let mut ready: BTreeSet<usize> = (0..count).filter(|&i| pending[i] == 0).collect();
+        let mut order = Vec::with_capacity(count);
+
+        while let Some(index) = ready.pop_first() {
+            order.push(Id::from(index));
+
+            for &dependent in &dependents[index] {
+                pending[dependent] -= 1;
+
+                if pending[dependent] == 0 {
+                    ready.insert(dependent);
+                }
+            }
+        }
+
+        match pending.iter().position(|&count| count > 0) {
+            Some(step) => Err(ValidationError::DependencyCycle { step }),
+            None => Ok(order),
+        }
+    }
+
+    /// Like [`Plan::validate`], but treats warnings as errors.
+    /// # Errors
+    ///
+    /// Returns the first problem or warning found.
+    pub fn validate_strict(&self) -> Result<(), ValidationError> {
+        match self.validate()?.into_iter().next() {
+            Some(warning) => Err(warning.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+fn validate_program(step: usize, path: PathBuf) -> Result<(), ValidationError> {
+    let mut magic = [0; 4];
+
+    let read = File::open(&path).and_then(|mut file| file.read_exact(&mut magic));
+
+    match read {
+        Ok(()) if &magic == WASM_MAGIC => Ok(()),
+        Ok(()) => Err(ValidationError::InvalidWasm { step, path }),
+        Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
+            Err(ValidationError::InvalidWasm { step, path })
+        }
+        Err(source) => Err(ValidationError::ProgramNotFound { step, path, source }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Step;
+
+    #[test]
+    fn rejects_non_wasm_program() {
+        let path = std::env::temp_dir().join(format!(
+            "simulator-validate-not-wasm-{}.wasm",
+            std::process::id()
+        ));
+        std::fs::write(&path, b"not a wasm file").unwrap();
+
+        let mut plan = Plan::new("alice");
+        plan.register_existing_key("alice");
+        plan.add_step(Step::create_program(&path));
+        let result = plan.validate();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            result,
+            Err(ValidationError::InvalidWasm { step: 0, path: invalid }) if invalid == path
+        ));
+
+        plan.steps[0] = Step::create_program(&path);
+        assert!(matches!(
+            plan.validate(),
+            Err(ValidationError::ProgramNotFound { step: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn resolves_programs_in_dir() {
+        let dir = std::env::temp_dir();
+        let name = format!("simulator-validate-in-{}.wasm", std::process::id());
+        std::fs::write(dir.join(&name), b"\0asm").unwrap();
+
+        let mut plan = Plan::new("alice");
+        plan.register_existing_key("alice");
+        plan.add_step(Step::create_program(&name));
+        let in_dir = plan.validate_in(&dir);
+        let in_cwd = plan.validate();
+
+        std::fs::remove_file(dir.join(&name)).unwrap();
+
+        assert!(in_dir.is_ok());
+        assert!(matches!(
+            in_cwd,
+            Err(ValidationError::ProgramNotFound { step: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_method() {
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::new(Endpoint::Execute, "trans fer", 1000, vec![]));
+
+        assert!(matches!(
+            plan.validate(),
+            Err(ValidationError::InvalidMethod { step: 0, method }) if method == "trans fer"
+        ));
+    }
+
+    #[test]
+    fn rejects_wrong_param_count() {
+        let transfer = |params| Step::new(Endpoint::Execute, "transfer", 1000, params);
+        let program = Param::Id(0.into());
+
+        let mut plan = Plan::new("alice");
+        plan.register_existing_key("alice");
+        plan.register_signature("transfer", 2);
+        plan.add_step(transfer(vec![
+            program.clone(),
+            "bob".to_string().into(),
+            10u64.into(),
+        ]));
+        assert!(plan.validate().is_ok());
+
+        plan.add_step(transfer(vec![program, 10u64.into()]));
+        assert!(matches!(
+            plan.validate(),
+            Err(ValidationError::ParamCount { step: 1, method, expected: 2, actual: 1 })
+                if method == "transfer"
+        ));
+    }
+
+    #[test]
+    fn orders_and_checks_dependencies() {
+        let key = |name: &str| Step::create_key(crate::Key::Ed25519(name.into()));
+
+        let mut plan = Plan::new("alice");
+        let alice = plan.add_step(key("alice"));
+        let bob = plan.add_step(key("bob").depends_on(&[Id::from(2)]));
+        let carol = plan.add_step(key("carol").depends_on(&[alice]));
+
+        assert_eq!(plan.execution_order().unwrap(), [alice, carol, bob]);
+        assert!(plan.validate().is_ok());
+
+        plan.steps[0] = key("alice").depends_on(&[bob]);
+        assert!(matches!(
+            plan.validate(),
+            Err(ValidationError::DependencyCycle { step: 0 })
+        ));
+
+        plan.steps[0] = key("alice").depends_on(&[Id::from(3)]);
+        assert!(matches!(
+            plan.execution_order(),
+            Err(ValidationError::UnknownDependency {
+                step: 0,
+                dependency: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn warns_on_zero_max_units() {
+        let call = |endpoint, max_units| Step::new(endpoint, "inc", max_units, vec![]);
+
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::create_key(crate::Key::Ed25519("alice".into())));
+        plan.add_step(call(Endpoint::ReadOnly, 0));
+        plan.add_step(call(Endpoint::Execute, 1000));
+
+        assert_eq!(plan.validate().unwrap(), []);
+        assert!(plan.validate_strict().is_ok());
+
+        plan.add_step(call(Endpoint::Execute, 0));
+
+        assert_eq!(
+            plan.validate().unwrap(),
+            [ValidationWarning::ZeroMaxUnits { step: 3 }]
+        );
+        assert!(matches!(
+            plan.validate_strict(),
+            Err(ValidationError::Warning(ValidationWarning::ZeroMaxUnits {
+                step: 3
+            }))
+        ));
+    }
+
+    #[test]
+    fn warns_on_read_only_max_units() {
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::create_key(crate::Key::Ed25519("alice".into())));
+        plan.add_step(Step::new(Endpoint::ReadOnly, "get_value", 1000, vec![]));
+
+        assert_eq!(
+            plan.validate().unwrap(),
+            [ValidationWarning::ReadOnlyMaxUnits {
+                step: 1,
+                max_units: 1000
+            }]
+        );
+
+        plan.steps[1].max_units = 0;
+        assert_eq!(plan.validate().unwrap(), []);
+    }
+
+    #[test]
+    fn rejects_unknown_caller() {
+        let key = |name: &str| Step::create_key(crate::Key::Ed25519(name.into()));
+
+        let mut plan = Plan::new("alice");
+        plan.add_step(key("bob"));
+        assert_eq!(plan.validate().unwrap(), []);
+
+        plan.add_step(Step::new(Endpoint::ReadOnly, "balance", 0, vec![]));
+        assert!(matches!(
+            plan.validate(),
+            Err(ValidationError::UnknownCaller { step: 1, caller_key }) if caller_key == "alice"
+        ));
+
+        plan.register_existing_key("alice");
+        assert_eq!(plan.validate().unwrap(), []);
+    }
+
+    #[test]
+    fn rejects_calls_before_caller_is_created() {
+        let key = |name: &str| Step::create_key(crate::Key::Ed25519(name.into()));
+
+        let mut plan = Plan::new("alice");
+        plan.add_step(Step::new(Endpoint::ReadOnly, "balance", 0, vec![]));
+        plan.add_step(key("alice"));
+        assert!(matches!(
+            plan.validate(),
+            Err(ValidationError::UnknownCaller { step: 0, .. })
+        ));
+
+        plan.insert_step(0, key("alice"));
+        assert_eq!(plan.validate().unwrap(), []);
+    }
+
+    #[test]
+    fn warns_on_foreign_ids() {
+        let read = |id: Id| Step::new(Endpoint::ReadOnly, "get_value", 0, vec![id.into()]);
+
+        let key = |name: &str| Step::create_key(crate::Key::Ed25519(name.into()));
+
+        let mut other = Plan::new("alice");
+        let foreign = other.add_step(key("bob"));
+
+        let mut plan = Plan::new("alice");
+        let alice = plan.add_step(key("alice"));
+        assert_eq!(foreign, alice);
+
+        plan.add_step(read(alice));
+        plan.add_step(read(Id::from(0)));
+        assert_eq!(plan.validate().unwrap(), []);
+
+        plan.add_step(read(foreign));
+        assert_eq!(
+            plan.validate().unwrap(),
+            [ValidationWarning::ForeignId {
+                step: 3,
+                id: foreign
+            }]
+        );
+
+        // clones share the ids of the plan
+        assert_eq!(plan.clone().validate().unwrap().len(), 1);
+    }
+}
@@ -14,7 +14,7 @@ pub fn get_value(_: Context, external: Program, address: Address) -> u64 {
 
 #[cfg(test)]
 mod tests {
-    use simulator::{ClientBuilder, Endpoint, Key, Param, Step};
+    use simulator::{ClientBuilder, Key, Param, Step};
 
     const PROGRAM_PATH: &str = env!("PROGRAM_PATH");
 
@@ -51,29 +51,13 @@ mod tests {
         let params = vec![counter_external, counter.clone(), owner_key.clone()];
 
         simulator
-            .run_step::<()>(
-                &owner,
-                &Step {
-                    endpoint: Endpoint::Execute,
-                    method: "inc".into(),
-                    max_units: 100_000_000,
-                    params: params.clone(),
-                },
-            )
+            .execute::<()>(&owner, "inc", 100_000_000, params.clone())
             .expect("call inc");
 
-        let response = simulator
-            .run_step::<u64>(
-                &owner,
-                &Step {
-                    endpoint: Endpoint::ReadOnly,
-                    method: "get_value".into(),
-                    max_units: 1_000_000,
-                    params,
-                },
-            )
+        let response: u64 = simulator
+            .read(&owner, "get_value", params)
             .expect("call get_value");
 
-        assert_eq!(response.result.response, 1u64);
+        assert_eq!(response, 1);
     }
 }
@@ -63,6 +63,7 @@ pub fn get_value_external(_: Context, target: Program, max_units: Gas, of: Addre
 #[cfg(test)]
 mod tests {
     use simulator::{Endpoint, Key, Param, Plan, Step};
+    use std::{path::Path, time::Duration};
 
     const PROGRAM_PATH: &str = env!("PROGRAM_PATH");
 
@@ -77,19 +78,19 @@ mod tests {
 
         plan.add_step(Step::create_key(Key::Ed25519(owner_key.clone())));
 
-        plan.add_step(Step {
-            endpoint: Endpoint::Key,
-            method: "key_create".into(),
-            params: vec![alice_key.clone()],
-            max_units: 0,
-        });
+        plan.add_step(Step::new(
+            Endpoint::Key,
+            "key_create",
+            0,
+            vec![alice_key.clone()],
+        ));
 
-        plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "program_create".into(),
-            max_units: 1_000_000,
-            params: vec![Param::String(PROGRAM_PATH.into())],
-        });
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "program_create",
+            1_000_000,
+            vec![Param::String(PROGRAM_PATH.into())],
+        ));
 
         let plan_responses = simulator.run_plan(plan).unwrap();
 
@@ -115,26 +116,26 @@ mod tests {
 
         plan.add_step(Step::create_key(Key::Ed25519(owner_key.clone())));
 
-        plan.add_step(Step {
-            endpoint: Endpoint::Key,
-            method: "key_create".into(),
-            params: vec![bob_key.clone()],
-            max_units: 0,
-        });
-
-        let counter_id = plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "program_create".into(),
-            max_units: 1_000_000,
-            params: vec![Param::String(PROGRAM_PATH.into())],
-        });
-
-        plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "inc".into(),
-            max_units: 1_000_000,
-            params: vec![counter_id.into(), bob_key.clone(), 10.into()],
-        });
+        plan.add_step(Step::new(
+            Endpoint::Key,
+            "key_create",
+            0,
+            vec![bob_key.clone()],
+        ));
+
+        let counter_id = plan.add_step(Step::new(
+            Endpoint::Execute,
+            "program_create",
+            1_000_000,
+            vec![Param::String(PROGRAM_PATH.into())],
+        ));
+
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "inc",
+            1_000_000,
+            vec![counter_id.into(), bob_key.clone(), 10.into()],
+        ));
 
         let plan_responses = simulator.run_plan(plan).unwrap();
 
@@ -148,22 +149,37 @@ mod tests {
                 .unwrap()
         );
 
-        let value = simulator
-            .run_step::<u64>(
-                &owner_key,
-                &Step {
-                    endpoint: Endpoint::ReadOnly,
-                    method: "get_value".into(),
-                    max_units: 0,
-                    params: vec![counter_id.into(), bob_key.clone()],
-                },
-            )
-            .unwrap()
-            .result
-            .response;
+        let value: u64 = simulator
+            .read(&owner_key, "get_value", vec![counter_id.into(), bob_key])
+            .unwrap();
         assert_eq!(value, 10);
     }
 
+    #[test]
+    fn increment_is_deterministic() {
+        let mut simulator = simulator::ClientBuilder::new().try_build().unwrap();
+
+        let owner_key = String::from("owner");
+        let bob_key = Param::Key(Key::Ed25519(String::from("bob")));
+
+        let mut plan = Plan::new(&owner_key);
+
+        plan.add_step(Step::create_key(Key::Ed25519(owner_key.clone())));
+        plan.add_step(Step::create_key(Key::Ed25519(String::from("bob"))));
+        let counter_id = plan.add_step(Step::create_program(PROGRAM_PATH));
+
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "inc",
+            1_000_000,
+            vec![counter_id.into(), bob_key, 10.into()],
+        ));
+
+        simulator
+            .run_plan_twice_assert_deterministic(&plan)
+            .unwrap();
+    }
+
     #[test]
     fn external_call() {
         let mut simulator = simulator::ClientBuilder::new().try_build().unwrap();
@@ -175,26 +191,26 @@ mod tests {
 
         plan.add_step(Step::create_key(Key::Ed25519(owner_key.clone())));
 
-        plan.add_step(Step {
-            endpoint: Endpoint::Key,
-            method: "key_create".into(),
-            params: vec![bob_key.clone()],
-            max_units: 0,
-        });
-
-        let counter1_id = plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "program_create".into(),
-            max_units: 1_000_000,
-            params: vec![Param::String(PROGRAM_PATH.into())],
-        });
-
-        let counter2_id = plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "program_create".into(),
-            max_units: 1_000_000,
-            params: vec![Param::String(PROGRAM_PATH.into())],
-        });
+        plan.add_step(Step::new(
+            Endpoint::Key,
+            "key_create",
+            0,
+            vec![bob_key.clone()],
+        ));
+
+        let counter1_id = plan.add_step(Step::new(
+            Endpoint::Execute,
+            "program_create",
+            1_000_000,
+            vec![Param::String(PROGRAM_PATH.into())],
+        ));
+
+        let counter2_id = plan.add_step(Step::new(
+            Endpoint::Execute,
+            "program_create",
+            1_000_000,
+            vec![Param::String(PROGRAM_PATH.into())],
+        ));
 
         let plan_responses = simulator.run_plan(plan).unwrap();
 
@@ -211,54 +227,184 @@ mod tests {
         let value = simulator
             .run_step::<u64>(
                 &owner_key,
-                &Step {
-                    endpoint: Endpoint::ReadOnly,
-                    method: "get_value".into(),
-                    max_units: 0,
-                    params: vec![counter2_id.into(), bob_key.clone()],
-                },
+                &Step::new(
+                    Endpoint::ReadOnly,
+                    "get_value",
+                    0,
+                    vec![counter2_id.into(), bob_key.clone()],
+                ),
             )
             .unwrap()
             .result
             .response;
-        assert_eq!(value, 0);
+        assert_eq!(value, Some(0));
 
         simulator
             .run_step::<bool>(
                 &owner_key,
-                &Step {
-                    endpoint: Endpoint::Execute,
-                    method: "inc_external".into(),
-                    max_units: 100_000_000,
-                    params: vec![
+                &Step::new(
+                    Endpoint::Execute,
+                    "inc_external",
+                    100_000_000,
+                    vec![
                         counter1_id.into(),
                         counter2_id.into(),
                         1_000_000.into(),
                         bob_key.clone(),
                         10.into(),
                     ],
-                },
+                ),
             )
             .unwrap();
 
         let value = simulator
             .run_step::<u64>(
                 &owner_key,
-                &Step {
-                    endpoint: Endpoint::ReadOnly,
-                    method: "get_value_external".into(),
-                    max_units: 0,
-                    params: vec![
+                &Step::new(
+                    Endpoint::ReadOnly,
+                    "get_value_external",
+                    0,
+                    vec![
                         counter1_id.into(),
                         counter2_id.into(),
                         1_000_000.into(),
                         bob_key.clone(),
                     ],
-                },
+                ),
             )
             .unwrap()
             .result
             .response;
-        assert_eq!(value, 10);
+        assert_eq!(value, Some(10));
+    }
+
+    #[test]
+    fn respawn_after_exit() {
+        let mut simulator = simulator::ClientBuilder::new().try_build().unwrap();
+
+        let owner_key = String::from("owner");
+
+        // the simulator exits on a step it cannot verify, here a read-only
+        // call without a program id
+        simulator
+            .read::<u64>(&owner_key, "get_value", vec![Param::U64(0)])
+            .unwrap_err();
+
+        simulator.ensure_alive().unwrap();
+
+        let mut plan = Plan::new(&owner_key);
+
+        plan.add_step(Step::create_key(Key::Ed25519(owner_key.clone())));
+        plan.add_step(Step::create_program(PROGRAM_PATH));
+
+        let plan_responses = simulator.run_plan(plan).unwrap();
+
+        assert!(plan_responses.iter().all(|resp| resp.base.error.is_none()));
+    }
+
+    #[test]
+    fn create_program_from_relative_path() {
+        let program_path = Path::new(PROGRAM_PATH);
+        let program_dir = program_path.parent().unwrap();
+        let program_file = program_path.file_name().unwrap().to_str().unwrap();
+
+        let mut simulator = simulator::ClientBuilder::new()
+            .current_dir(program_dir)
+            .try_build()
+            .unwrap();
+
+        let owner_key = String::from("owner");
+        let mut plan = Plan::new(&owner_key);
+
+        plan.add_step(Step::create_key(Key::Ed25519(owner_key.clone())));
+        plan.add_step(Step::create_program(program_file));
+
+        let plan_responses = simulator.run_plan(plan).unwrap();
+
+        assert!(plan_responses.iter().all(|resp| resp.base.error.is_none()));
+    }
+
+    #[test]
+    fn clients_from_one_builder() {
+        let builder = simulator::ClientBuilder::new().read_timeout(Duration::from_secs(30));
+
+        for mut simulator in [builder.try_build(), builder.clone().try_build()].map(Result::unwrap)
+        {
+            let owner_key = String::from("owner");
+            let mut plan = Plan::new(&owner_key);
+
+            plan.add_step(Step::create_key(Key::Ed25519(owner_key.clone())));
+            plan.add_step(Step::create_program(PROGRAM_PATH));
+
+            let plan_responses = simulator.run_plan(plan).unwrap();
+
+            // each client is a separate session, numbering its steps from 0
+            assert_eq!(plan_responses[1].base.id, 1);
+            assert!(plan_responses.iter().all(|resp| resp.base.error.is_none()));
+        }
+    }
+
+    #[test]
+    fn tee_simulator_output() {
+        let transcript = std::env::temp_dir().join(format!(
+            "counter-tee-simulator-output-{}.log",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&transcript);
+
+        let mut simulator = simulator::ClientBuilder::new()
+            .tee_stdout(&transcript)
+            .try_build()
+            .unwrap();
+
+        let owner_key = String::from("owner");
+        let mut plan = Plan::new(&owner_key);
+
+        plan.add_step(Step::create_key(Key::Ed25519(owner_key.clone())));
+        plan.add_step(Step::create_program(PROGRAM_PATH));
+
+        let plan_responses = simulator.run_plan(plan).unwrap();
+        let output = std::fs::read_to_string(&transcript).unwrap();
+
+        assert_eq!(plan_responses.len(), 2);
+        assert!(output.contains(r#""id":0"#));
+        assert!(output.contains(r#""id":1"#));
+    }
+
+    #[test]
+    fn sequential_plans_share_state() {
+        let mut simulator = simulator::ClientBuilder::new().try_build().unwrap();
+
+        let owner_key = String::from("owner");
+        let bob_key = Param::Key(Key::Ed25519(String::from("bob")));
+
+        let mut deploy = Plan::new(&owner_key);
+        deploy.add_step(Step::create_key(Key::Ed25519(owner_key.clone())));
+        deploy.create_keys([Key::Ed25519(String::from("bob"))]);
+        let counter_id = deploy.add_step(Step::create_program(PROGRAM_PATH));
+
+        // the plans run as one, so the second plan can use the id of the
+        // counter created by the first
+        let mut increment = Plan::new(&owner_key);
+        increment.add_step(Step::new(
+            Endpoint::Execute,
+            "inc",
+            1_000_000,
+            vec![counter_id.into(), bob_key.clone(), 5.into()],
+        ));
+
+        let plan_responses = simulator
+            .run_plans_sequential(vec![deploy, increment])
+            .unwrap();
+
+        assert!(plan_responses
+            .iter()
+            .flatten()
+            .all(|resp| resp.base.error.is_none()));
+
+        let value: u64 = simulator
+            .read(&owner_key, "get_value", vec![counter_id.into(), bob_key])
+            .unwrap();
+        assert_eq!(value, 5);
     }
 }
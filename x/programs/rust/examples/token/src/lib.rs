@@ -201,19 +201,19 @@ mod tests {
         let mut plan = Plan::new(&owner_key_id);
 
         plan.add_step(Step::create_key(owner_key.clone()));
-        let program_id = plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "program_create".into(),
-            max_units: 0,
-            params: vec![Param::String(PROGRAM_PATH.into())],
-        });
-
-        plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "init".into(),
-            params: vec![program_id.into()],
-            max_units: 1000000,
-        });
+        let program_id = plan.add_step(Step::new(
+            Endpoint::Execute,
+            "program_create",
+            0,
+            vec![Param::String(PROGRAM_PATH.into())],
+        ));
+
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "init",
+            1000000,
+            vec![program_id.into()],
+        ));
 
         let plan_responses = simulator.run_plan(plan).unwrap();
 
@@ -230,18 +230,18 @@ mod tests {
         let supply = simulator
             .run_step::<u64>(
                 &owner_key_id,
-                &Step {
-                    endpoint: Endpoint::ReadOnly,
-                    method: "get_total_supply".into(),
-                    max_units: 0,
-                    params: vec![program_id.into()],
-                },
+                &Step::new(
+                    Endpoint::ReadOnly,
+                    "get_total_supply",
+                    0,
+                    vec![program_id.into()],
+                ),
             )
             .unwrap()
             .result
             .response;
 
-        assert_eq!(supply, INITIAL_SUPPLY);
+        assert_eq!(supply, Some(INITIAL_SUPPLY));
     }
 
     #[test]
@@ -259,37 +259,37 @@ mod tests {
 
         plan.add_step(Step::create_key(Key::Ed25519(owner_key_id.clone())));
 
-        let program_id = plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "program_create".into(),
-            max_units: 0,
-            params: vec![Param::String(PROGRAM_PATH.into())],
-        });
-
-        plan.add_step(Step {
-            endpoint: Endpoint::Key,
-            method: "key_create".into(),
-            params: vec![alice_key.clone()],
-            max_units: 0,
-        });
-
-        plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "init".into(),
-            params: vec![program_id.into()],
-            max_units: 1000000,
-        });
-
-        plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "mint_to".into(),
-            params: vec![
+        let program_id = plan.add_step(Step::new(
+            Endpoint::Execute,
+            "program_create",
+            0,
+            vec![Param::String(PROGRAM_PATH.into())],
+        ));
+
+        plan.add_step(Step::new(
+            Endpoint::Key,
+            "key_create",
+            0,
+            vec![alice_key.clone()],
+        ));
+
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "init",
+            1000000,
+            vec![program_id.into()],
+        ));
+
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "mint_to",
+            1000000,
+            vec![
                 program_id.into(),
                 alice_key.clone(),
                 Param::U64(alice_initial_balance),
             ],
-            max_units: 1000000,
-        });
+        ));
 
         let plan_responses = simulator.run_plan(plan).unwrap();
 
@@ -306,18 +306,18 @@ mod tests {
         let balance = simulator
             .run_step::<u64>(
                 &owner_key_id,
-                &Step {
-                    endpoint: Endpoint::ReadOnly,
-                    method: "get_balance".into(),
-                    max_units: 0,
-                    params: vec![program_id.into(), alice_key],
-                },
+                &Step::new(
+                    Endpoint::ReadOnly,
+                    "get_balance",
+                    0,
+                    vec![program_id.into(), alice_key],
+                ),
             )
             .unwrap()
             .result
             .response;
 
-        assert_eq!(balance, alice_initial_balance);
+        assert_eq!(balance, Some(alice_initial_balance));
     }
 
     #[test]
@@ -337,56 +337,56 @@ mod tests {
 
         plan.add_step(Step::create_key(Key::Ed25519(owner_key_id.clone())));
 
-        let program_id = plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "program_create".into(),
-            max_units: 0,
-            params: vec![Param::String(PROGRAM_PATH.into())],
-        });
-
-        plan.add_step(Step {
-            endpoint: Endpoint::Key,
-            method: "key_create".into(),
-            params: vec![alice_key.clone()],
-            max_units: 0,
-        });
-
-        plan.add_step(Step {
-            endpoint: Endpoint::Key,
-            method: "key_create".into(),
-            params: vec![bob_key.clone()],
-            max_units: 0,
-        });
-
-        plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "init".into(),
-            params: vec![program_id.into()],
-            max_units: 1000000,
-        });
-
-        plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "mint_to".into(),
-            params: vec![
+        let program_id = plan.add_step(Step::new(
+            Endpoint::Execute,
+            "program_create",
+            0,
+            vec![Param::String(PROGRAM_PATH.into())],
+        ));
+
+        plan.add_step(Step::new(
+            Endpoint::Key,
+            "key_create",
+            0,
+            vec![alice_key.clone()],
+        ));
+
+        plan.add_step(Step::new(
+            Endpoint::Key,
+            "key_create",
+            0,
+            vec![bob_key.clone()],
+        ));
+
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "init",
+            1000000,
+            vec![program_id.into()],
+        ));
+
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "mint_to",
+            1000000,
+            vec![
                 program_id.into(),
                 alice_key.clone(),
                 Param::U64(alice_initial_balance),
             ],
-            max_units: 1000000,
-        });
+        ));
 
-        plan.add_step(Step {
-            endpoint: Endpoint::Execute,
-            method: "transfer".into(),
-            params: vec![
+        plan.add_step(Step::new(
+            Endpoint::Execute,
+            "transfer",
+            1000000,
+            vec![
                 program_id.into(),
                 alice_key.clone(),
                 bob_key.clone(),
                 Param::U64(transfer_amount),
             ],
-            max_units: 1000000,
-        });
+        ));
 
         let plan_responses = simulator.run_plan(plan).unwrap();
 
@@ -403,76 +403,76 @@ mod tests {
         let supply = simulator
             .run_step::<u64>(
                 &owner_key_id,
-                &Step {
-                    endpoint: Endpoint::ReadOnly,
-                    method: "get_total_supply".into(),
-                    max_units: 0,
-                    params: vec![program_id.into()],
-                },
+                &Step::new(
+                    Endpoint::ReadOnly,
+                    "get_total_supply",
+                    0,
+                    vec![program_id.into()],
+                ),
             )
             .unwrap()
             .result
             .response;
-        assert_eq!(supply, INITIAL_SUPPLY);
+        assert_eq!(supply, Some(INITIAL_SUPPLY));
 
         let balance = simulator
             .run_step::<u64>(
                 &owner_key_id,
-                &Step {
-                    endpoint: Endpoint::ReadOnly,
-                    method: "get_balance".into(),
-                    max_units: 0,
-                    params: vec![program_id.into(), alice_key.clone()],
-                },
+                &Step::new(
+                    Endpoint::ReadOnly,
+                    "get_balance",
+                    0,
+                    vec![program_id.into(), alice_key.clone()],
+                ),
             )
             .unwrap()
             .result
             .response;
-        assert_eq!(balance, post_transfer_balance);
+        assert_eq!(balance, Some(post_transfer_balance));
 
         let balance = simulator
             .run_step::<u64>(
                 &owner_key_id,
-                &Step {
-                    endpoint: Endpoint::ReadOnly,
-                    method: "get_balance".into(),
-                    max_units: 0,
-                    params: vec![program_id.into(), bob_key],
-                },
+                &Step::new(
+                    Endpoint::ReadOnly,
+                    "get_balance",
+                    0,
+                    vec![program_id.into(), bob_key],
+                ),
             )
             .unwrap()
             .result
             .response;
-        assert_eq!(balance, transfer_amount);
+        assert_eq!(balance, Some(transfer_amount));
 
         let balance = simulator
             .run_step::<u64>(
                 &owner_key_id,
-                &Step {
-                    endpoint: Endpoint::Execute,
-                    method: "burn_from".into(),
-                    params: vec![program_id.into(), alice_key.clone()],
-                    max_units: 1000000,
-                },
+                &Step::new(
+                    Endpoint::Execute,
+                    "burn_from",
+                    1000000,
+                    vec![program_id.into(), alice_key.clone()],
+                ),
             )
             .unwrap()
             .result
             .response;
-        assert_eq!(balance, post_transfer_balance);
+        assert_eq!(balance, Some(post_transfer_balance));
 
         let balance = simulator
             .run_step::<u64>(
                 &owner_key_id,
-                &Step {
-                    endpoint: Endpoint::ReadOnly,
-                    method: "get_balance".into(),
-                    max_units: 0,
-                    params: vec![program_id.into(), alice_key],
-                },
+                &Step::new(
+                    Endpoint::ReadOnly,
+                    "get_balance",
+                    0,
+                    vec![program_id.into(), alice_key],
+                ),
             )
             .unwrap()
             .result
             .response;
-        assert_eq!(balance, 0);
+        assert_eq!(balance, Some(0));
     }
 }